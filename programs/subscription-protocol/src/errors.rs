@@ -113,4 +113,226 @@ pub enum ErrorCodes {
     
     #[msg("Jupiter Lend withdraw failed")]
     JupiterLendWithdrawFailed,
+
+    #[msg("Vault buffer does not belong to the yield vault")]
+    InvalidVaultBuffer,
+
+    #[msg("Only the plan merchant can perform this action")]
+    UnauthorizedMerchant,
+
+    #[msg("Merchant plan is already active")]
+    PlanAlreadyActive,
+
+    #[msg("Merchant plan still has active subscribers")]
+    PlanHasSubscribers,
+
+    #[msg("Only the subscriber can perform this action")]
+    UnauthorizedSubscriber,
+
+    #[msg("A price change is still pending for this plan")]
+    PriceChangePending,
+
+    #[msg("Price change must take effect in the future and before its cutoff")]
+    InvalidPriceChangeSchedule,
+
+    #[msg("No new price to accept")]
+    NoPriceChangeToAccept,
+
+    #[msg("Trial period cannot be negative")]
+    InvalidTrialPeriod,
+
+    #[msg("Trial usage account is required for plans with a trial")]
+    TrialUsageRequired,
+
+    #[msg("Payment accounts are required to charge this plan")]
+    PaymentAccountsRequired,
+
+    #[msg("Missed period policy must allow at least one period")]
+    InvalidMissedPeriodPolicy,
+
+    #[msg("Dunning grace period and retry interval cannot be negative")]
+    InvalidDunningConfig,
+
+    #[msg("Subscription is suspended")]
+    SubscriptionSuspended,
+
+    #[msg("Retry interval has not elapsed yet")]
+    RetryTooEarly,
+
+    #[msg("Wallet can cover the payment; execute it instead")]
+    PaymentCoverable,
+
+    #[msg("Yield vault accounts are required for wallets with yield shares")]
+    YieldAccountsRequired,
+
+    #[msg("Keeper reward exceeds 100% of the charge")]
+    InvalidKeeperReward,
+
+    #[msg("Batch accounts must be (subscription, wallet, wallet token account, plan, payees) groups for the merchant and mint")]
+    InvalidBatchAccounts,
+
+    #[msg("Subscription is cancelled before this period; nothing to charge")]
+    ChargeAfterCancellation,
+
+    #[msg("Cancellation time must not be in the past")]
+    InvalidCancellationTime,
+
+    #[msg("No cancellation is scheduled")]
+    NoCancellationScheduled,
+
+    #[msg("Scheduled cancellation has already taken effect")]
+    CancellationAlreadyEffective,
+
+    #[msg("Scheduled cancellation time has not been reached")]
+    CancellationNotDue,
+
+    #[msg("Final payment must be settled before the subscription is closed")]
+    FinalPaymentOutstanding,
+
+    #[msg("Settle outstanding payments before changing plans")]
+    PaymentOutstanding,
+
+    #[msg("Plans must use the same billing mode")]
+    BillingModeMismatch,
+
+    #[msg("Refund amount must be greater than zero")]
+    InvalidRefundAmount,
+
+    #[msg("Refund exceeds the amount paid")]
+    RefundExceedsPaid,
+
+    #[msg("Refund accounts are required for plans with prorated refunds")]
+    RefundAccountsRequired,
+
+    #[msg("Pause policy must allow a positive maximum pause")]
+    InvalidPausePolicy,
+
+    #[msg("Subscription is paused")]
+    SubscriptionPaused,
+
+    #[msg("Plan does not allow pausing")]
+    PauseNotAllowed,
+
+    #[msg("Pause length must be positive and within the plan maximum")]
+    InvalidPauseLength,
+
+    #[msg("Subscription is not paused")]
+    SubscriptionNotPaused,
+
+    #[msg("Only the subscriber can resume before the pause ends")]
+    PauseNotOver,
+
+    #[msg("Charge exceeds the subscriber's per-charge limit")]
+    ChargeLimitExceeded,
+
+    #[msg("Metered plans need a positive usage cap")]
+    InvalidMeteringConfig,
+
+    #[msg("Plan is not metered")]
+    PlanNotMetered,
+
+    #[msg("Usage exceeds the plan's cap for this period")]
+    UsageCapExceeded,
+
+    #[msg("Usage units must be greater than zero")]
+    InvalidUsageUnits,
+
+    #[msg("Quantity must be at least one")]
+    InvalidQuantity,
+
+    #[msg("Coupon code too long (max 32 characters)")]
+    CouponCodeTooLong,
+
+    #[msg("Coupon needs a valid discount and a positive duration")]
+    InvalidCoupon,
+
+    #[msg("Coupon has expired or reached its redemption limit")]
+    CouponNotRedeemable,
+
+    #[msg("Coupon does not belong to this merchant and mint")]
+    CouponMismatch,
+
+    #[msg("Payee token accounts must match the plan's revenue splits")]
+    InvalidPayeeAccounts,
+
+    #[msg("Revenue splits need distinct payees with non-zero shares summing to 10,000 bps (max 5)")]
+    InvalidRevenueSplits,
+
+    #[msg("Mints with a transfer hook are not supported")]
+    UnsupportedMintExtension,
+
+    #[msg("Only native SOL plans and wallets can wrap or unwrap SOL")]
+    NativeMintRequired,
+
+    #[msg("Native SOL deposits and payouts need the system program and SOL accounts")]
+    NativeSolAccountsRequired,
+
+    #[msg("A user token account is required for token deposits")]
+    TokenAccountRequired,
+
+    #[msg("Oracle pricing needs a positive staleness window and a confidence bound of 1 to 10,000 bps")]
+    InvalidOraclePricing,
+
+    #[msg("Oracle-priced plans must bill in arrears")]
+    OraclePricingRequiresArrears,
+
+    #[msg("Plans priced in a reference currency and in tokens can't be mixed")]
+    PricingCurrencyMismatch,
+
+    #[msg("Oracle-priced plans need the plan's price feed and mint")]
+    PriceFeedRequired,
+
+    #[msg("Price feed does not match the plan or is not a verified Pyth price update")]
+    InvalidPriceFeed,
+
+    #[msg("Oracle price is not positive")]
+    InvalidOraclePrice,
+
+    #[msg("Oracle price is too old")]
+    StaleOraclePrice,
+
+    #[msg("Oracle price confidence interval is too wide")]
+    OracleConfidenceTooWide,
+
+    #[msg("Oracle price moved beyond the subscriber's slippage bound")]
+    PriceSlippageExceeded,
+
+    #[msg("Wallet label too long (max 32 characters)")]
+    WalletLabelTooLong,
+
+    #[msg("Wallet is already on the current layout")]
+    WalletAlreadyMigrated,
+
+    #[msg("Cancel all subscriptions on this wallet before closing it")]
+    WalletHasActiveSubscriptions,
+
+    #[msg("Merchant plan is already on the current layout")]
+    PlanAlreadyMigrated,
+
+    #[msg("Plans with published price versions change price through publish_price_change")]
+    PriceVersioned,
+
+    #[msg("Jupiter fToken account does not belong to the yield vault")]
+    InvalidFTokenAccount,
+
+    #[msg("Protocol config is already on the current layout")]
+    ProtocolConfigAlreadyMigrated,
+
+    #[msg("Subscription wallet does not match the subscription")]
+    SubscriptionWalletMismatch,
+
+    #[msg("Undo the scheduled cancellation before changing plans")]
+    CancellationScheduled,
+
+    #[msg("Merchant's refund allowance can't cover the prorated refund; schedule the cancellation instead")]
+    RefundAllowanceInsufficient,
+
+    #[msg("Native SOL payouts can't be combined with revenue splits")]
+    UnwrapSolWithRevenueSplits,
+
+    #[msg("Plan does not settle in this mint")]
+    SettlementMintNotSupported,
+
+    #[msg("Protocol fee reversal needs the treasury token account and the config authority")]
+    FeeReversalAccountsRequired,
 }
\ No newline at end of file
@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use crate::KeeperReward;
 
 #[event]
 pub struct ProtocolInitialized {
@@ -20,11 +21,23 @@ pub struct ProtocolFeeUpdated {
     pub new_fee_bps: u16,
 }
 
+#[event]
+pub struct KeeperRewardUpdated {
+    pub keeper_reward: KeeperReward,
+}
+
+#[event]
+pub struct ProtocolConfigMigrated {
+    pub authority: Pubkey,
+}
+
 #[event]
 pub struct SubscriptionWalletCreated {
     pub wallet_pda: Pubkey,
     pub owner: Pubkey,
     pub mint: Pubkey,
+    pub wallet_index: u16,
+    pub label: String,
 }
 
 #[event]
@@ -87,6 +100,51 @@ pub struct MerchantPlanRegistered {
     pub plan_pda: Pubkey,
 }
 
+#[event]
+pub struct MerchantPlanUpdated {
+    pub plan_pda: Pubkey,
+    pub plan_name: String,
+    pub fee_amount: u64,
+    pub payment_interval: i64,
+    pub trial_period_seconds: i64,
+}
+
+#[event]
+pub struct MerchantPlanPaused {
+    pub plan_pda: Pubkey,
+    pub merchant: Pubkey,
+}
+
+#[event]
+pub struct MerchantPlanResumed {
+    pub plan_pda: Pubkey,
+    pub merchant: Pubkey,
+}
+
+#[event]
+pub struct MerchantPlanClosed {
+    pub plan_pda: Pubkey,
+    pub merchant: Pubkey,
+    pub plan_id: String,
+}
+
+#[event]
+pub struct MerchantPlanMigrated {
+    pub plan_pda: Pubkey,
+    pub merchant: Pubkey,
+    pub plan_id: String,
+}
+
+#[event]
+pub struct PriceChangePublished {
+    pub plan_pda: Pubkey,
+    pub price_version: u32,
+    pub old_fee_amount: u64,
+    pub new_fee_amount: u64,
+    pub effective_at: i64,
+    pub cutoff_at: i64,
+}
+
 #[event]
 pub struct SubscriptionCreated {
     pub subscription_pda: Pubkey,
@@ -94,9 +152,84 @@ pub struct SubscriptionCreated {
     pub wallet: Pubkey,
     pub merchant: Pubkey,
     pub plan_id: String,
+    pub subscription_index: u16,
+    pub quantity: u32,
     pub session_token: String,
 }
 
+#[event]
+pub struct TrialStarted {
+    pub subscription_pda: Pubkey,
+    pub user: Pubkey,
+    pub merchant: Pubkey,
+    pub plan_pda: Pubkey,
+    pub trial_ends_at: i64,
+}
+
+#[event]
+pub struct TrialConverted {
+    pub subscription_pda: Pubkey,
+    pub user: Pubkey,
+    pub merchant: Pubkey,
+    pub plan_pda: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PaymentSkipReason {
+    NotDue,
+    InsufficientFunds,
+    Inactive,
+    PriceActionRequired,
+    CancellationScheduled,
+    Paused,
+    ChargeLimitExceeded,
+    Expired,
+    PriceSlippageExceeded,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExpiryReason {
+    MaxTotalSpend,
+    MaxPayments,
+}
+
+/// One payee's share of a charge on a plan with revenue splits
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PayeePayout {
+    pub payee: Pubkey,
+    pub token_account: Pubkey,
+    pub amount: u64,
+}
+
+/// How a charge on an oracle-priced plan was converted into the plan's mint
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct OracleConversion {
+    /// Amount owed in the reference currency, before any proration credit
+    pub reference_amount: u64,
+    pub price_feed: Pubkey,
+    pub price: i64,
+    pub confidence: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TerminationReason {
+    Abuse,
+    PlanSunset,
+    FailedKyc,
+    Other,
+}
+
+#[event]
+pub struct PaymentSkipped {
+    pub subscription_pda: Pubkey,
+    pub user: Pubkey,
+    pub merchant: Pubkey,
+    pub reason: PaymentSkipReason,
+}
+
 #[event]
 pub struct PaymentExecuted {
     pub subscription_pda: Pubkey,
@@ -107,6 +240,113 @@ pub struct PaymentExecuted {
     pub protocol_fee: u64,
     pub merchant_received: u64,
     pub payment_number: u32,
+    pub periods_settled: u32,
+    pub keeper: Option<Pubkey>,
+    pub keeper_reward: u64,
+    pub usage_units: u64,
+    pub included_units: u64,
+    pub overage_amount: u64,
+    pub discount_amount: u64,
+    pub coupon: Option<Pubkey>,
+    /// Breakdown of `merchant_received` across revenue split payees
+    pub payee_payouts: Vec<PayeePayout>,
+    /// Price used to convert the charge (oracle-priced plans)
+    pub oracle_conversion: Option<OracleConversion>,
+}
+
+#[event]
+pub struct SubscriptionPastDue {
+    pub subscription_pda: Pubkey,
+    pub user: Pubkey,
+    pub merchant: Pubkey,
+    pub periods_outstanding: u32,
+}
+
+#[event]
+pub struct PaymentFailed {
+    pub subscription_pda: Pubkey,
+    pub wallet_pda: Pubkey,
+    pub user: Pubkey,
+    pub merchant: Pubkey,
+    pub amount_due: u64,
+    pub attempt: u8,
+    pub next_retry_at: i64,
+}
+
+#[event]
+pub struct SubscriptionSuspended {
+    pub subscription_pda: Pubkey,
+    pub user: Pubkey,
+    pub merchant: Pubkey,
+    pub failed_attempts: u8,
+    pub past_due_since: i64,
+}
+
+#[event]
+pub struct SubscriptionReactivated {
+    pub subscription_pda: Pubkey,
+    pub user: Pubkey,
+    pub merchant: Pubkey,
+}
+
+#[event]
+pub struct PlanChanged {
+    pub old_subscription_pda: Pubkey,
+    pub subscription_pda: Pubkey,
+    pub user: Pubkey,
+    pub merchant: Pubkey,
+    pub old_plan: Pubkey,
+    pub new_plan: Pubkey,
+    pub old_fee_amount: u64,
+    pub new_fee_amount: u64,
+    pub prorated_charge: u64,
+    pub prorated_credit: u64,
+}
+
+#[event]
+pub struct SubscriptionMigrated {
+    pub legacy_pda: Pubkey,
+    pub subscription_pda: Pubkey,
+    pub user: Pubkey,
+    pub plan_pda: Pubkey,
+    pub subscription_index: u16,
+}
+
+#[event]
+pub struct SubscriptionWalletClosed {
+    pub wallet_pda: Pubkey,
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub wallet_index: u16,
+    pub shares_redeemed: u64,
+    pub swept_amount: u64,
+}
+
+#[event]
+pub struct SubscriptionWalletMigrated {
+    pub wallet_pda: Pubkey,
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+}
+
+#[event]
+pub struct RefundIssued {
+    pub subscription_pda: Pubkey,
+    pub user: Pubkey,
+    pub merchant: Pubkey,
+    pub payment_number: u32,
+    pub amount: u64,
+    pub protocol_fee_reversed: u64,
+    pub prorated: bool,
+}
+
+#[event]
+pub struct SubscriptionTerminated {
+    pub subscription_pda: Pubkey,
+    pub user: Pubkey,
+    pub merchant: Pubkey,
+    pub reason: TerminationReason,
+    pub refund_amount: u64,
 }
 
 #[event]
@@ -116,4 +356,112 @@ pub struct SubscriptionCancelled {
     pub user: Pubkey,
     pub merchant: Pubkey,
     pub payments_made: u32,
+}
+
+#[event]
+pub struct CouponCreated {
+    pub coupon_pda: Pubkey,
+    pub merchant: Pubkey,
+    pub mint: Pubkey,
+    pub code: String,
+}
+
+#[event]
+pub struct CouponApplied {
+    pub subscription_pda: Pubkey,
+    pub coupon_pda: Pubkey,
+    pub user: Pubkey,
+    pub merchant: Pubkey,
+    pub duration_periods: u16,
+}
+
+#[event]
+pub struct QuantityUpdated {
+    pub subscription_pda: Pubkey,
+    pub user: Pubkey,
+    pub merchant: Pubkey,
+    pub old_quantity: u32,
+    pub new_quantity: u32,
+    pub prorated_charge: u64,
+    pub prorated_credit: u64,
+}
+
+#[event]
+pub struct UsageReported {
+    pub subscription_pda: Pubkey,
+    pub merchant: Pubkey,
+    pub units: u64,
+    pub period_usage: u64,
+}
+
+#[event]
+pub struct SpendingLimitsUpdated {
+    pub subscription_pda: Pubkey,
+    pub user: Pubkey,
+    pub max_amount_per_charge: u64,
+    pub max_total_spend: u64,
+    pub max_payments: u32,
+    pub max_price_slippage_bps: u16,
+}
+
+#[event]
+pub struct SubscriptionExpired {
+    pub subscription_pda: Pubkey,
+    pub user: Pubkey,
+    pub merchant: Pubkey,
+    pub reason: ExpiryReason,
+    pub total_paid: u64,
+    pub payment_count: u32,
+}
+
+#[event]
+pub struct SubscriptionPaused {
+    pub subscription_pda: Pubkey,
+    pub user: Pubkey,
+    pub merchant: Pubkey,
+    pub paused_until: i64,
+}
+
+#[event]
+pub struct SubscriptionResumed {
+    pub subscription_pda: Pubkey,
+    pub user: Pubkey,
+    pub merchant: Pubkey,
+    pub paused_seconds: i64,
+    pub next_payment_due: i64,
+}
+
+#[event]
+pub struct CancellationScheduled {
+    pub subscription_pda: Pubkey,
+    pub user: Pubkey,
+    pub merchant: Pubkey,
+    pub cancel_at: i64,
+    pub at_period_end: bool,
+}
+
+#[event]
+pub struct CancellationUndone {
+    pub subscription_pda: Pubkey,
+    pub user: Pubkey,
+    pub merchant: Pubkey,
+}
+
+#[event]
+pub struct PriceChangeAccepted {
+    pub subscription_pda: Pubkey,
+    pub user: Pubkey,
+    pub plan_pda: Pubkey,
+    pub price_version: u32,
+    pub fee_amount: u64,
+    pub effective_at: i64,
+}
+
+#[event]
+pub struct PriceChangeActionRequired {
+    pub subscription_pda: Pubkey,
+    pub user: Pubkey,
+    pub merchant: Pubkey,
+    pub price_version: u32,
+    pub new_fee_amount: u64,
 }
\ No newline at end of file
@@ -1,3 +1,6 @@
+// Every instruction module exports a `handler`; lib.rs calls them by full path
+#![allow(ambiguous_glob_reexports)]
+
 pub mod protocol;
 pub mod wallet;
 pub mod yield_ops;
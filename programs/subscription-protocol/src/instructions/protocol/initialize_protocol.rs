@@ -1,5 +1,5 @@
 use anchor_lang::prelude::*;
-use crate::{ProtocolConfig, ProtocolInitialized, ErrorCodes};
+use crate::{ProtocolConfig, KeeperReward, ProtocolInitialized, ErrorCodes};
 
 #[derive(Accounts)]
 pub struct InitializeProtocol<'info> {
@@ -31,6 +31,7 @@ pub fn handler(
     config.authority = ctx.accounts.authority.key();
     config.protocol_fee_bps = protocol_fee_bps;
     config.treasury = ctx.accounts.treasury.key();
+    config.keeper_reward = KeeperReward::Disabled;
     config.bump = ctx.bumps.protocol_config;
 
     emit!(ProtocolInitialized {
@@ -1,5 +1,5 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, TokenAccount};
+use anchor_spl::token_interface::{Mint, TokenAccount};
 use crate::{YieldVault, YieldVaultInitialized, ErrorCodes};
 
 #[derive(Accounts)]
@@ -16,13 +16,13 @@ pub struct InitializeYieldVault<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
 
-    pub mint: Account<'info, Mint>,
+    pub mint: InterfaceAccount<'info, Mint>,
 
     /// USDC buffer token account (owned by yield_vault PDA)
-    pub usdc_buffer: Account<'info, TokenAccount>,
+    pub usdc_buffer: InterfaceAccount<'info, TokenAccount>,
 
     /// Jupiter Lend fToken account (owned by yield_vault PDA)
-    pub jupiter_ftoken_account: Account<'info, TokenAccount>,
+    pub jupiter_ftoken_account: InterfaceAccount<'info, TokenAccount>,
 
     /// Jupiter Lend lending account
     /// CHECK: Jupiter Lend protocol account
@@ -0,0 +1,68 @@
+use anchor_lang::prelude::*;
+use crate::{
+    ProtocolConfig, LegacyProtocolConfig, KeeperReward, ProtocolConfigMigrated, ErrorCodes
+};
+use crate::utils::grow_account;
+
+#[derive(Accounts)]
+pub struct MigrateProtocolConfig<'info> {
+    /// CHECK: Config created before `keeper_reward` was appended; the legacy
+    /// layout is deserialized in the handler
+    #[account(
+        mut,
+        seeds = [b"protocol_config"],
+        bump,
+        owner = crate::ID,
+    )]
+    pub protocol_config: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<MigrateProtocolConfig>) -> Result<()> {
+    let config_info = ctx.accounts.protocol_config.to_account_info();
+    let full_len = 8 + ProtocolConfig::INIT_SPACE;
+    require!(
+        config_info.data_len() < full_len,
+        ErrorCodes::ProtocolConfigAlreadyMigrated
+    );
+
+    let legacy = {
+        let data = config_info.try_borrow_data()?;
+        require!(
+            data.len() >= 8 && data[..8] == *ProtocolConfig::DISCRIMINATOR,
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+        LegacyProtocolConfig::deserialize(&mut &data[8..])?
+    };
+
+    require!(
+        legacy.authority == ctx.accounts.authority.key(),
+        ErrorCodes::UnauthorizedProtocolUpdate
+    );
+
+    let config = ProtocolConfig {
+        authority: legacy.authority,
+        treasury: legacy.treasury,
+        protocol_fee_bps: legacy.protocol_fee_bps,
+        bump: legacy.bump,
+        keeper_reward: KeeperReward::Disabled,
+    };
+
+    grow_account(
+        &config_info,
+        &ctx.accounts.authority.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        full_len,
+    )?;
+    config.try_serialize(&mut &mut config_info.try_borrow_mut_data()?[..])?;
+
+    emit!(ProtocolConfigMigrated {
+        authority: config.authority,
+    });
+
+    Ok(())
+}
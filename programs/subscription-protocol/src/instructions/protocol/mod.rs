@@ -1,7 +1,13 @@
+#![allow(ambiguous_glob_reexports)]
+
 pub mod initialize_protocol;
 pub mod initialize_yield_vault;
 pub mod update_protocol_fee;
+pub mod update_keeper_reward;
+pub mod migrate_protocol_config;
 
 pub use initialize_protocol::*;
 pub use initialize_yield_vault::*;
-pub use update_protocol_fee::*;
\ No newline at end of file
+pub use update_protocol_fee::*;
+pub use update_keeper_reward::*;
+pub use migrate_protocol_config::*;
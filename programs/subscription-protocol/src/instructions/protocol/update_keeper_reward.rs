@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+use crate::{ProtocolConfig, KeeperReward, KeeperRewardUpdated, ErrorCodes};
+
+#[derive(Accounts)]
+pub struct UpdateKeeperReward<'info> {
+    #[account(
+        mut,
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump,
+        has_one = authority @ ErrorCodes::UnauthorizedProtocolUpdate
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(
+    ctx: Context<UpdateKeeperReward>,
+    keeper_reward: KeeperReward,
+) -> Result<()> {
+    require!(keeper_reward.is_valid(), ErrorCodes::InvalidKeeperReward);
+
+    let config = &mut ctx.accounts.protocol_config;
+    config.keeper_reward = keeper_reward;
+
+    emit!(KeeperRewardUpdated {
+        keeper_reward,
+    });
+
+    Ok(())
+}
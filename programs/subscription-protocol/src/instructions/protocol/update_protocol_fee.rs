@@ -26,7 +26,7 @@ pub fn handler(
 
     emit!(ProtocolFeeUpdated {
         old_fee_bps: old_fee,
-        new_fee_bps: new_fee_bps,
+        new_fee_bps,
     });
 
     Ok(())
@@ -0,0 +1,53 @@
+use anchor_lang::prelude::*;
+use crate::{SubscriptionState, MerchantPlan, PriceChangeAccepted, ErrorCodes};
+
+#[derive(Accounts)]
+pub struct AcceptPriceChange<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            subscription_state.user.as_ref(),
+            subscription_state.merchant_plan.as_ref(),
+            &subscription_state.subscription_index.to_le_bytes()
+        ],
+        bump = subscription_state.bump,
+        has_one = user @ ErrorCodes::UnauthorizedSubscriber,
+        constraint = subscription_state.is_active @ ErrorCodes::SubscriptionInactive,
+    )]
+    pub subscription_state: Account<'info, SubscriptionState>,
+
+    #[account(
+        constraint = merchant_plan.key() == subscription_state.merchant_plan @ ErrorCodes::InvalidMerchantPlan,
+    )]
+    pub merchant_plan: Account<'info, MerchantPlan>,
+
+    pub user: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<AcceptPriceChange>) -> Result<()> {
+    let subscription = &mut ctx.accounts.subscription_state;
+    let merchant_plan = &ctx.accounts.merchant_plan;
+
+    require!(
+        subscription.accepted_price_version < merchant_plan.price_version,
+        ErrorCodes::NoPriceChangeToAccept
+    );
+
+    // The new fee is applied on the first payment after the effective date
+    subscription.accepted_price_version = merchant_plan.price_version;
+    subscription.accepted_fee_amount = merchant_plan.pending_fee_amount;
+    subscription.accepted_effective_at = merchant_plan.price_effective_at;
+    subscription.price_action_required = false;
+
+    emit!(PriceChangeAccepted {
+        subscription_pda: subscription.key(),
+        user: subscription.user,
+        plan_pda: merchant_plan.key(),
+        price_version: merchant_plan.price_version,
+        fee_amount: merchant_plan.pending_fee_amount,
+        effective_at: merchant_plan.price_effective_at,
+    });
+
+    Ok(())
+}
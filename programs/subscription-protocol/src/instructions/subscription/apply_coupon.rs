@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+use crate::{SubscriptionState, Coupon, CouponApplied, ErrorCodes};
+
+#[derive(Accounts)]
+pub struct ApplyCoupon<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            subscription_state.user.as_ref(),
+            subscription_state.merchant_plan.as_ref(),
+            &subscription_state.subscription_index.to_le_bytes()
+        ],
+        bump = subscription_state.bump,
+        has_one = merchant @ ErrorCodes::UnauthorizedMerchant,
+        constraint = subscription_state.is_active @ ErrorCodes::SubscriptionInactive,
+    )]
+    pub subscription_state: Account<'info, SubscriptionState>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"coupon",
+            coupon.merchant.as_ref(),
+            coupon.mint.as_ref(),
+            coupon.code.as_bytes()
+        ],
+        bump = coupon.bump,
+        has_one = merchant @ ErrorCodes::CouponMismatch,
+        constraint = coupon.mint == subscription_state.mint @ ErrorCodes::CouponMismatch,
+    )]
+    pub coupon: Account<'info, Coupon>,
+
+    pub merchant: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<ApplyCoupon>) -> Result<()> {
+    let subscription = &mut ctx.accounts.subscription_state;
+    let coupon = &mut ctx.accounts.coupon;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    require!(coupon.is_redeemable(current_time), ErrorCodes::CouponNotRedeemable);
+
+    coupon.redemptions = coupon.redemptions
+        .checked_add(1)
+        .ok_or(ErrorCodes::MathOverflow)?;
+    subscription.apply_coupon(coupon.key(), coupon.discount, coupon.duration_periods);
+
+    emit!(CouponApplied {
+        subscription_pda: subscription.key(),
+        coupon_pda: coupon.key(),
+        user: subscription.user,
+        merchant: subscription.merchant,
+        duration_periods: coupon.duration_periods,
+    });
+
+    Ok(())
+}
@@ -1,7 +1,12 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenInterface, TokenAccount};
 use crate::{
-    SubscriptionState, SubscriptionWallet, MerchantPlan,
-    SubscriptionCancelled, ErrorCodes
+    SubscriptionState, SubscriptionWallet, MerchantPlan, ProtocolConfig,
+    SubscriptionCancelled, RefundIssued, ErrorCodes
+};
+use crate::utils::{
+    unused_period_amount, calculate_refund, delegated_refund_allowance,
+    transfer_as_plan_delegate, reverse_protocol_fee
 };
 
 #[derive(Accounts)]
@@ -11,8 +16,8 @@ pub struct CancelSubscriptionWallet<'info> {
         seeds = [
             b"subscription",
             subscription_state.user.as_ref(),
-            subscription_state.merchant.as_ref(),
-            subscription_state.mint.as_ref()
+            subscription_state.merchant_plan.as_ref(),
+            &subscription_state.subscription_index.to_le_bytes()
         ],
         bump = subscription_state.bump,
         has_one = user @ ErrorCodes::UnauthorizedCancellation,
@@ -26,21 +31,137 @@ pub struct CancelSubscriptionWallet<'info> {
     )]
     pub subscription_wallet: Account<'info, SubscriptionWallet>,
 
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = merchant_plan.key() == subscription_state.merchant_plan @ ErrorCodes::InvalidMerchantPlan,
+    )]
     pub merchant_plan: Account<'info, MerchantPlan>,
 
     #[account(mut)]
     pub user: Signer<'info>,
+
+    // Refund accounts (required when the plan refunds unused time on cancel)
+    #[account(
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump,
+    )]
+    pub protocol_config: Option<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        mut,
+        token::mint = subscription_state.mint,
+        constraint = merchant_token_account.owner == subscription_state.merchant @ ErrorCodes::InvalidMerchantAccount
+    )]
+    pub merchant_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = subscription_state.mint,
+        token::authority = subscription_wallet
+    )]
+    pub wallet_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = subscription_state.mint)]
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    // Protocol fee reversal (required when the plan's refund policy reverses the fee)
+    #[account(
+        mut,
+        token::mint = subscription_state.mint,
+    )]
+    pub protocol_treasury: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub protocol_authority: Option<Signer<'info>>,
 }
 
 pub fn handler(ctx: Context<CancelSubscriptionWallet>) -> Result<()> {
     let subscription = &ctx.accounts.subscription_state;
-    let wallet = &mut ctx.accounts.subscription_wallet;
-    let merchant_plan = &mut ctx.accounts.merchant_plan;
+    let merchant_plan = &ctx.accounts.merchant_plan;
     
-    require!(subscription.is_active, ErrorCodes::SubscriptionInactive);
+    require!(
+        subscription.is_active || subscription.is_expired(),
+        ErrorCodes::SubscriptionInactive
+    );
+
+    if merchant_plan.refund_policy.prorate_on_cancel {
+        let current_time = Clock::get()?.unix_timestamp;
+        let amount = unused_period_amount(subscription, merchant_plan, current_time)?;
+
+        if amount > 0 {
+            let protocol_config = ctx.accounts.protocol_config
+                .as_ref()
+                .ok_or(ErrorCodes::RefundAccountsRequired)?;
+            let merchant_token_account = ctx.accounts.merchant_token_account
+                .as_ref()
+                .ok_or(ErrorCodes::RefundAccountsRequired)?;
+            let wallet_token_account = ctx.accounts.wallet_token_account
+                .as_ref()
+                .ok_or(ErrorCodes::RefundAccountsRequired)?;
+            let mint = ctx.accounts.mint
+                .as_ref()
+                .ok_or(ErrorCodes::RefundAccountsRequired)?;
+            let token_program = ctx.accounts.token_program
+                .as_ref()
+                .ok_or(ErrorCodes::RefundAccountsRequired)?;
 
+            let (refund, protocol_fee_reversed) = calculate_refund(
+                amount,
+                protocol_config,
+                &merchant_plan.refund_policy,
+            )?;
+
+            // protocol_config is optional here, so the reversal accounts are checked by hand
+            if let Some(protocol_treasury) = ctx.accounts.protocol_treasury.as_ref() {
+                require_keys_eq!(protocol_treasury.owner, protocol_config.treasury, ErrorCodes::InvalidTreasuryAccount);
+            }
+            if let Some(protocol_authority) = ctx.accounts.protocol_authority.as_ref() {
+                require_keys_eq!(protocol_authority.key(), protocol_config.authority, ErrorCodes::UnauthorizedProtocolUpdate);
+            }
+
+            // The refund is paid in full or not at all. Subscribers whose
+            // merchant has withdrawn the allowance can still leave through
+            // schedule_cancellation, which ends at the period boundary.
+            require!(
+                delegated_refund_allowance(merchant_plan, merchant_token_account) >= refund,
+                ErrorCodes::RefundAllowanceInsufficient
+            );
+
+            transfer_as_plan_delegate(
+                merchant_plan,
+                merchant_token_account,
+                wallet_token_account,
+                mint,
+                token_program,
+                refund,
+            )?;
+            reverse_protocol_fee(
+                ctx.accounts.protocol_treasury.as_ref(),
+                ctx.accounts.protocol_authority.as_ref(),
+                wallet_token_account,
+                mint,
+                token_program,
+                protocol_fee_reversed,
+            )?;
+
+            emit!(RefundIssued {
+                subscription_pda: subscription.key(),
+                user: subscription.user,
+                merchant: subscription.merchant,
+                payment_number: subscription.payment_count,
+                amount: refund + protocol_fee_reversed,
+                protocol_fee_reversed,
+                prorated: true,
+            });
+        }
+    }
+
+    let wallet = &mut ctx.accounts.subscription_wallet;
     wallet.total_subscriptions = wallet.total_subscriptions.saturating_sub(1);
+    wallet.release(subscription.committed_amount);
+
+    let merchant_plan = &mut ctx.accounts.merchant_plan;
     merchant_plan.total_subscribers = merchant_plan.total_subscribers.saturating_sub(1);
 
     emit!(SubscriptionCancelled {
@@ -52,4 +173,4 @@ pub fn handler(ctx: Context<CancelSubscriptionWallet>) -> Result<()> {
     });
 
     Ok(())
-}
\ No newline at end of file
+}
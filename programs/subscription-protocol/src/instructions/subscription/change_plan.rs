@@ -0,0 +1,310 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenInterface, TokenAccount};
+use crate::{
+    SubscriptionState, SubscriptionWallet, MerchantPlan, ProtocolConfig,
+    PlanChanged, ErrorCodes
+};
+use crate::utils::{
+    calculate_subscription_commitment, calculate_period_proration, split_charge, transfer_payment,
+    PaymentAccounts, payment_cost, quote_for_plan, settlement_amount, resume_lapsed_pause,
+    merchant_payout_account, unwrap_sol_payout
+};
+
+#[derive(Accounts)]
+#[instruction(new_subscription_index: u16)]
+pub struct ChangePlan<'info> {
+    // Subscription PDAs are seeded by plan, so the state moves to a new account
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            subscription_state.user.as_ref(),
+            subscription_state.merchant_plan.as_ref(),
+            &subscription_state.subscription_index.to_le_bytes()
+        ],
+        bump = subscription_state.bump,
+        has_one = user @ ErrorCodes::UnauthorizedSubscriber,
+        constraint = subscription_state.is_active @ ErrorCodes::SubscriptionInactive,
+        constraint = !subscription_state.is_suspended @ ErrorCodes::SubscriptionSuspended,
+        constraint = !subscription_state.is_paused(Clock::get()?.unix_timestamp) @ ErrorCodes::SubscriptionPaused,
+        close = user
+    )]
+    pub subscription_state: Account<'info, SubscriptionState>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + SubscriptionState::INIT_SPACE,
+        seeds = [
+            b"subscription",
+            user.key().as_ref(),
+            new_plan.key().as_ref(),
+            &new_subscription_index.to_le_bytes()
+        ],
+        bump
+    )]
+    pub new_subscription_state: Account<'info, SubscriptionState>,
+
+    #[account(
+        mut,
+        constraint = subscription_wallet.key() == subscription_state.subscription_wallet
+    )]
+    pub subscription_wallet: Account<'info, SubscriptionWallet>,
+
+    #[account(
+        mut,
+        constraint = current_plan.key() == subscription_state.merchant_plan @ ErrorCodes::InvalidMerchantPlan,
+    )]
+    pub current_plan: Account<'info, MerchantPlan>,
+
+    #[account(
+        mut,
+        constraint = new_plan.is_active @ ErrorCodes::PlanInactive,
+        constraint = new_plan.key() != current_plan.key() @ ErrorCodes::InvalidMerchantPlan,
+        constraint = new_plan.merchant == current_plan.merchant @ ErrorCodes::InvalidMerchantPlan,
+        constraint = new_plan.settles_in(&subscription_state.mint) @ ErrorCodes::SettlementMintNotSupported,
+    )]
+    pub new_plan: Account<'info, MerchantPlan>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        mut,
+        token::mint = subscription_wallet.mint,
+        token::authority = subscription_wallet
+    )]
+    pub wallet_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = subscription_state.mint,
+        constraint = merchant_token_account.owner == current_plan.merchant @ ErrorCodes::InvalidMerchantAccount
+    )]
+    pub merchant_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = subscription_state.mint,
+        constraint = protocol_treasury.owner == protocol_config.treasury @ ErrorCodes::InvalidTreasuryAccount
+    )]
+    pub protocol_treasury: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = subscription_state.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Pyth price update, validated against the new plan's oracle settings
+    /// (required between oracle-priced plans)
+    pub price_feed: Option<UncheckedAccount<'info>>,
+
+    // Native SOL payout (plans with unwrap_sol): the merchant's share passes through
+    // this temporary wSOL account and is paid to `merchant` as SOL
+    #[account(
+        init,
+        payer = user,
+        seeds = [b"sol_unwrap", new_plan.merchant.as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = sol_unwrap_account,
+        token::token_program = token_program,
+    )]
+    pub sol_unwrap_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = new_plan.merchant @ ErrorCodes::InvalidMerchantAccount
+    )]
+    pub merchant: Option<SystemAccount<'info>>,
+}
+
+// remaining_accounts: one payee token account per revenue split on the new plan,
+// needed only when the change is charged
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ChangePlan<'info>>,
+    new_subscription_index: u16,
+) -> Result<()> {
+    let current_plan = &ctx.accounts.current_plan;
+    let new_plan = &ctx.accounts.new_plan;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    resume_lapsed_pause(&mut ctx.accounts.subscription_state, current_time)?;
+    let mut subscription = (*ctx.accounts.subscription_state).clone();
+
+    require!(
+        !subscription.is_past_due && !subscription.is_payment_due(current_time),
+        ErrorCodes::PaymentOutstanding
+    );
+    require!(
+        !subscription.has_scheduled_cancellation(),
+        ErrorCodes::CancellationScheduled
+    );
+    require!(
+        new_plan.billing_mode == current_plan.billing_mode,
+        ErrorCodes::BillingModeMismatch
+    );
+    require!(
+        new_plan.is_oracle_priced() == current_plan.is_oracle_priced(),
+        ErrorCodes::PricingCurrencyMismatch
+    );
+
+    let old_fee = subscription.fee_amount;
+    let new_fee = new_plan.current_fee(current_time);
+    let new_interval = new_plan.payment_interval;
+    let new_period_fee = subscription.scaled_fee(new_fee)
+        .ok_or(ErrorCodes::MathOverflow)?;
+
+    // Price the rest of the current period at the new plan's rate. Trials
+    // carry over unchanged with nothing to prorate.
+    let (prorated_charge, prorated_credit) = calculate_period_proration(
+        &subscription,
+        current_plan.billing_mode,
+        subscription.period_fee().ok_or(ErrorCodes::MathOverflow)?,
+        new_period_fee,
+        new_interval,
+        current_time,
+    )?;
+
+    // Oracle-priced plans settle the proration in tokens at today's price
+    let quote = quote_for_plan(
+        new_plan,
+        ctx.accounts.price_feed.as_ref().map(|account| account.as_ref()),
+        Some(&ctx.accounts.mint),
+        current_time,
+    )?;
+    let prorated_charge = settlement_amount(quote.as_ref(), prorated_charge)?;
+    let prorated_credit = settlement_amount(quote.as_ref(), prorated_credit)?;
+    let new_commitment = settlement_amount(
+        quote.as_ref(),
+        calculate_subscription_commitment(new_period_fee)?,
+    )?;
+
+    let wallet = &mut ctx.accounts.subscription_wallet;
+
+    // As when subscribing, the wallet must hold the new plan's buffer on top
+    // of whatever the change charges now
+    let split = split_charge(prorated_charge, &ctx.accounts.protocol_config, None)?;
+    let charge_cost = if prorated_charge > 0 {
+        payment_cost(
+            new_plan,
+            &ctx.accounts.mint,
+            split.merchant_receives,
+            &[split.treasury_receives],
+        )?
+    } else {
+        0
+    };
+    require!(
+        ctx.accounts.wallet_token_account.amount
+            >= new_commitment.checked_add(charge_cost).ok_or(ErrorCodes::MathOverflow)?,
+        ErrorCodes::InsufficientWalletBalance
+    );
+
+    if prorated_charge > 0 {
+        transfer_payment(
+            wallet,
+            &PaymentAccounts {
+                wallet_token_account: &ctx.accounts.wallet_token_account,
+                merchant_token_account: merchant_payout_account(
+                    new_plan,
+                    &ctx.accounts.merchant_token_account,
+                    ctx.accounts.sol_unwrap_account.as_ref(),
+                )?,
+                protocol_treasury: &ctx.accounts.protocol_treasury,
+                payee_token_accounts: ctx.remaining_accounts,
+                mint: &ctx.accounts.mint,
+                token_program: &ctx.accounts.token_program,
+            },
+            new_plan,
+            split.merchant_receives,
+            split.treasury_receives,
+        )?;
+
+        subscription.total_paid = subscription.total_paid
+            .checked_add(prorated_charge)
+            .ok_or(ErrorCodes::MathOverflow)?;
+        // The prorated charge pays for the current period, so it is refundable
+        // along with that period's payment
+        subscription.last_charge_amount = subscription.last_charge_amount
+            .checked_add(prorated_charge)
+            .ok_or(ErrorCodes::MathOverflow)?;
+        wallet.total_spent = wallet.total_spent
+            .checked_add(prorated_charge)
+            .ok_or(ErrorCodes::MathOverflow)?;
+    }
+
+    unwrap_sol_payout(
+        ctx.accounts.sol_unwrap_account.as_ref(),
+        ctx.bumps.sol_unwrap_account,
+        ctx.accounts.merchant.as_ref(),
+        Some(&ctx.accounts.user),
+        Some(&ctx.accounts.system_program),
+        &ctx.accounts.token_program,
+    )?;
+
+    subscription.proration_credit = subscription.proration_credit
+        .checked_add(prorated_credit)
+        .ok_or(ErrorCodes::MathOverflow)?;
+
+    // Keep the billing anchor: the next charge lands when it would have
+    if !subscription.is_in_trial() {
+        subscription.last_payment_timestamp = subscription.next_payment_due()
+            .checked_sub(new_interval)
+            .ok_or(ErrorCodes::MathOverflow)?;
+    }
+
+    wallet.recommit(subscription.committed_amount, new_commitment)?;
+
+    let old_subscription_pda = ctx.accounts.subscription_state.key();
+    subscription.merchant_plan = new_plan.key();
+    subscription.fee_amount = new_fee;
+    subscription.payment_interval = new_interval;
+    subscription.metering = new_plan.metering;
+    subscription.committed_amount = new_commitment;
+    subscription.subscription_index = new_subscription_index;
+    subscription.bump = ctx.bumps.new_subscription_state;
+
+    // Same price-version handling as a fresh subscription to the new plan
+    subscription.price_version = if current_time < new_plan.price_effective_at {
+        new_plan.price_version.saturating_sub(1)
+    } else {
+        new_plan.price_version
+    };
+    subscription.accepted_price_version = new_plan.price_version;
+    subscription.accepted_fee_amount = new_plan.pending_fee_amount;
+    subscription.accepted_effective_at = new_plan.price_effective_at;
+    subscription.price_action_required = false;
+
+    ctx.accounts.new_subscription_state.set_inner(subscription);
+
+    let current_plan = &mut ctx.accounts.current_plan;
+    current_plan.total_subscribers = current_plan.total_subscribers.saturating_sub(1);
+
+    let new_plan = &mut ctx.accounts.new_plan;
+    new_plan.total_subscribers = new_plan.total_subscribers
+        .checked_add(1)
+        .ok_or(ErrorCodes::MathOverflow)?;
+
+    emit!(PlanChanged {
+        old_subscription_pda,
+        subscription_pda: ctx.accounts.new_subscription_state.key(),
+        user: ctx.accounts.user.key(),
+        merchant: new_plan.merchant,
+        old_plan: current_plan.key(),
+        new_plan: new_plan.key(),
+        old_fee_amount: old_fee,
+        new_fee_amount: new_fee,
+        prorated_charge,
+        prorated_credit,
+    });
+
+    Ok(())
+}
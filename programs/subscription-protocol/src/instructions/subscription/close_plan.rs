@@ -0,0 +1,39 @@
+use anchor_lang::prelude::*;
+use crate::{MerchantPlan, MerchantPlanClosed, ErrorCodes};
+
+#[derive(Accounts)]
+pub struct ClosePlan<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"merchant_plan",
+            merchant_plan.merchant.as_ref(),
+            merchant_plan.mint.as_ref(),
+            merchant_plan.plan_id.as_bytes()
+        ],
+        bump = merchant_plan.bump,
+        has_one = merchant @ ErrorCodes::UnauthorizedMerchant,
+        close = merchant
+    )]
+    pub merchant_plan: Account<'info, MerchantPlan>,
+
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<ClosePlan>) -> Result<()> {
+    let merchant_plan = &ctx.accounts.merchant_plan;
+
+    require!(
+        merchant_plan.total_subscribers == 0,
+        ErrorCodes::PlanHasSubscribers
+    );
+
+    emit!(MerchantPlanClosed {
+        plan_pda: merchant_plan.key(),
+        merchant: merchant_plan.merchant,
+        plan_id: merchant_plan.plan_id.clone(),
+    });
+
+    Ok(())
+}
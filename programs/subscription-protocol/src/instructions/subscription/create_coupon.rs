@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+use crate::{Coupon, CouponDiscount, CouponCreated, ErrorCodes};
+
+#[derive(Accounts)]
+#[instruction(code: String)]
+pub struct CreateCoupon<'info> {
+    #[account(
+        init,
+        payer = merchant,
+        space = 8 + Coupon::INIT_SPACE,
+        seeds = [
+            b"coupon",
+            merchant.key().as_ref(),
+            mint.key().as_ref(),
+            code.as_bytes()
+        ],
+        bump
+    )]
+    pub coupon: Account<'info, Coupon>,
+
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(
+    ctx: Context<CreateCoupon>,
+    code: String,
+    discount: CouponDiscount,
+    duration_periods: u16,
+    max_redemptions: u32,
+    expires_at: i64,
+) -> Result<()> {
+    require!(code.len() <= 32, ErrorCodes::CouponCodeTooLong);
+    require!(
+        discount.is_valid() && duration_periods > 0,
+        ErrorCodes::InvalidCoupon
+    );
+    require!(expires_at >= 0, ErrorCodes::InvalidCoupon);
+
+    let coupon = &mut ctx.accounts.coupon;
+
+    coupon.merchant = ctx.accounts.merchant.key();
+    coupon.mint = ctx.accounts.mint.key();
+    coupon.code = code;
+    coupon.discount = discount;
+    coupon.duration_periods = duration_periods;
+    coupon.max_redemptions = max_redemptions;
+    coupon.redemptions = 0;
+    coupon.expires_at = expires_at;
+    coupon.bump = ctx.bumps.coupon;
+
+    emit!(CouponCreated {
+        coupon_pda: coupon.key(),
+        merchant: coupon.merchant,
+        mint: coupon.mint,
+        code: coupon.code.clone(),
+    });
+
+    Ok(())
+}
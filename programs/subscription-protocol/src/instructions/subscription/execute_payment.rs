@@ -1,12 +1,14 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_spl::token_interface::{Mint, TokenInterface, TokenAccount};
 use crate::{
     SubscriptionState, SubscriptionWallet, MerchantPlan, ProtocolConfig,
-    YieldVault, PaymentExecuted, ErrorCodes
+    YieldVault, ErrorCodes
 };
 use crate::utils::{
     get_vault_total_value, calculate_shares_for_withdrawal,
-    withdraw_from_vault_internal
+    withdraw_from_vault_internal, prepare_charge, PreparedCharge, split_charge, record_charge,
+    transfer_payment, PaymentAccounts, transfer_from_wallet, payment_cost, amount_with_transfer_fee,
+    merchant_payout_account, unwrap_sol_payout, quote_for_plan, resume_lapsed_pause
 };
 
 #[derive(Accounts)]
@@ -16,11 +18,12 @@ pub struct ExecutePaymentFromWallet<'info> {
         seeds = [
             b"subscription",
             subscription_state.user.as_ref(),
-            subscription_state.merchant.as_ref(),
-            subscription_state.mint.as_ref()
+            subscription_state.merchant_plan.as_ref(),
+            &subscription_state.subscription_index.to_le_bytes()
         ],
         bump = subscription_state.bump,
         constraint = subscription_state.is_active @ ErrorCodes::SubscriptionInactive,
+        constraint = !subscription_state.is_paused(Clock::get()?.unix_timestamp) @ ErrorCodes::SubscriptionPaused,
     )]
     pub subscription_state: Account<'info, SubscriptionState>,
 
@@ -29,7 +32,8 @@ pub struct ExecutePaymentFromWallet<'info> {
         seeds = [
             b"subscription_wallet",
             subscription_wallet.owner.as_ref(),
-            subscription_wallet.mint.as_ref()
+            subscription_wallet.mint.as_ref(),
+            &SubscriptionWallet::index_seed(subscription_wallet.wallet_index)
         ],
         bump = subscription_wallet.bump,
         constraint = subscription_wallet.key() == subscription_state.subscription_wallet
@@ -53,21 +57,21 @@ pub struct ExecutePaymentFromWallet<'info> {
         token::mint = subscription_wallet.mint,
         token::authority = subscription_wallet
     )]
-    pub wallet_token_account: Account<'info, TokenAccount>,
+    pub wallet_token_account: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
         token::mint = subscription_state.mint,
         constraint = merchant_token_account.owner == merchant_plan.merchant
     )]
-    pub merchant_token_account: Account<'info, TokenAccount>,
+    pub merchant_token_account: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
         token::mint = subscription_state.mint,
         constraint = protocol_treasury.owner == protocol_config.treasury
     )]
-    pub protocol_treasury: Account<'info, TokenAccount>,
+    pub protocol_treasury: InterfaceAccount<'info, TokenAccount>,
 
     // Optional yield vault accounts (if user has yield enabled)
     #[account(
@@ -78,15 +82,57 @@ pub struct ExecutePaymentFromWallet<'info> {
     pub yield_vault: Option<Account<'info, YieldVault>>,
 
     #[account(mut)]
-    pub vault_buffer: Option<Account<'info, TokenAccount>>,
+    pub vault_buffer: Option<InterfaceAccount<'info, TokenAccount>>,
 
     /// CHECK: Jupiter Lend lending account
     pub jupiter_lending: Option<AccountInfo<'info>>,
 
-    pub token_program: Program<'info, Token>,
+    // Optional keeper reward destination (anyone may crank payments)
+    #[account(
+        mut,
+        token::mint = subscription_state.mint,
+    )]
+    pub keeper_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = subscription_state.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Pyth price update, validated against the plan's oracle settings
+    /// (required for oracle-priced plans)
+    pub price_feed: Option<UncheckedAccount<'info>>,
+
+    // Native SOL payout (plans with unwrap_sol): the merchant's share passes through
+    // this temporary wSOL account and is paid to `merchant` as SOL
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"sol_unwrap", merchant_plan.merchant.as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = sol_unwrap_account,
+        token::token_program = token_program,
+    )]
+    pub sol_unwrap_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = merchant_plan.merchant @ ErrorCodes::InvalidMerchantAccount
+    )]
+    pub merchant: Option<SystemAccount<'info>>,
+
+    // Funds the temporary account's rent, which is returned in the same instruction
+    #[account(mut)]
+    pub payer: Option<Signer<'info>>,
+
+    pub system_program: Option<Program<'info, System>>,
 }
 
-pub fn handler(ctx: Context<ExecutePaymentFromWallet>) -> Result<()> {
+// remaining_accounts: one payee token account per plan revenue split, in split order
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ExecutePaymentFromWallet<'info>>,
+) -> Result<()> {
     let subscription = &mut ctx.accounts.subscription_state;
     let merchant_plan = &ctx.accounts.merchant_plan;
     let wallet = &mut ctx.accounts.subscription_wallet;
@@ -94,25 +140,45 @@ pub fn handler(ctx: Context<ExecutePaymentFromWallet>) -> Result<()> {
     let current_time = Clock::get()?.unix_timestamp;
     
     require!(subscription.is_active, ErrorCodes::SubscriptionInactive);
+    resume_lapsed_pause(subscription, current_time)?;
     
-    let time_since_last = current_time - subscription.last_payment_timestamp;
     require!(
-        time_since_last >= subscription.payment_interval,
+        subscription.is_payment_due(current_time),
         ErrorCodes::PaymentTooEarly
     );
+    require!(
+        subscription.billable_periods(current_time, merchant_plan.billing_mode) > 0,
+        ErrorCodes::ChargeAfterCancellation
+    );
+
+    let quote = quote_for_plan(
+        merchant_plan,
+        ctx.accounts.price_feed.as_ref().map(|account| account.as_ref()),
+        Some(&ctx.accounts.mint),
+        current_time,
+    )?;
+
+    let charge = match prepare_charge(subscription, wallet, merchant_plan, quote.as_ref(), current_time)? {
+        PreparedCharge::Due(charge) => charge,
+        PreparedCharge::ChargeLimitExceeded => return Err(ErrorCodes::ChargeLimitExceeded.into()),
+        PreparedCharge::PriceSlippageExceeded => return Err(ErrorCodes::PriceSlippageExceeded.into()),
+        PreparedCharge::PriceActionRequired | PreparedCharge::Expired => return Ok(()),
+    };
 
     // Calculate fees
-    let base_amount = subscription.fee_amount;
-    let protocol_fee = (base_amount as u128)
-        .checked_mul(protocol_config.protocol_fee_bps as u128)
-        .unwrap()
-        .checked_div(10_000)
-        .unwrap() as u64;
-    
-    let merchant_receives = base_amount.checked_sub(protocol_fee)
-        .ok_or(ErrorCodes::MathOverflow)?;
-    
-    let total_charge = base_amount;
+    let split = split_charge(
+        charge.amount,
+        protocol_config,
+        ctx.accounts.keeper_token_account.as_ref().map(|account| account.owner),
+    )?;
+
+    // What leaves the wallet, including any Token-2022 transfer fees
+    let total_charge = payment_cost(
+        merchant_plan,
+        &ctx.accounts.mint,
+        split.merchant_receives,
+        &[split.treasury_receives, split.keeper_reward],
+    )?;
 
     // Check if we need to redeem shares from yield vault
     let wallet_balance = ctx.accounts.wallet_token_account.amount;
@@ -120,13 +186,14 @@ pub fn handler(ctx: Context<ExecutePaymentFromWallet>) -> Result<()> {
         // Need to redeem shares
         let shortfall = total_charge.checked_sub(wallet_balance)
             .ok_or(ErrorCodes::MathOverflow)?;
+        let shortfall = amount_with_transfer_fee(&ctx.accounts.mint, shortfall)?;
         
         let vault = &mut ctx.accounts.yield_vault.as_mut().unwrap();
         
         // Calculate shares needed
         let total_vault_value = get_vault_total_value(
             ctx.accounts.jupiter_lending.clone().unwrap(),
-            &vault,
+            vault,
             ctx.accounts.vault_buffer.as_ref(),
             None,
         )?;
@@ -145,12 +212,14 @@ pub fn handler(ctx: Context<ExecutePaymentFromWallet>) -> Result<()> {
         // Withdraw from vault
         withdraw_from_vault_internal(
             vault.to_account_info(),
-            &vault,
+            vault,
             ctx.accounts.vault_buffer.as_ref().unwrap(),
             &ctx.accounts.wallet_token_account,
+            &ctx.accounts.mint,
             &ctx.accounts.token_program,
             shortfall,
         )?;
+        ctx.accounts.wallet_token_account.reload()?;
 
         // Update share balances
         wallet.yield_shares = wallet.yield_shares
@@ -166,75 +235,45 @@ pub fn handler(ctx: Context<ExecutePaymentFromWallet>) -> Result<()> {
     let final_balance = ctx.accounts.wallet_token_account.amount;
     require!(final_balance >= total_charge, ErrorCodes::InsufficientFunds);
 
-    // Create PDA signer
-    let owner_key = wallet.owner;
-    let mint_key = wallet.mint;
-    let bump = wallet.bump;
-    let seeds = &[
-        b"subscription_wallet",
-        owner_key.as_ref(),
-        mint_key.as_ref(),
-        &[bump],
-    ];
-    let signer_seeds = &[&seeds[..]];
-
-    // Transfer to merchant
-    if merchant_receives > 0 {
-        let transfer_merchant = Transfer {
-            from: ctx.accounts.wallet_token_account.to_account_info(),
-            to: ctx.accounts.merchant_token_account.to_account_info(),
-            authority: wallet.to_account_info(),
-        };
-        
-        let cpi_ctx = CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            transfer_merchant,
-            signer_seeds,
-        );
-        
-        token::transfer(cpi_ctx, merchant_receives)?;
-    }
+    let payee_payouts = transfer_payment(
+        wallet,
+        &PaymentAccounts {
+            wallet_token_account: &ctx.accounts.wallet_token_account,
+            merchant_token_account: merchant_payout_account(
+                merchant_plan,
+                &ctx.accounts.merchant_token_account,
+                ctx.accounts.sol_unwrap_account.as_ref(),
+            )?,
+            protocol_treasury: &ctx.accounts.protocol_treasury,
+            payee_token_accounts: ctx.remaining_accounts,
+            mint: &ctx.accounts.mint,
+            token_program: &ctx.accounts.token_program,
+        },
+        merchant_plan,
+        split.merchant_receives,
+        split.treasury_receives,
+    )?;
 
-    // Transfer protocol fee
-    if protocol_fee > 0 {
-        let transfer_protocol = Transfer {
-            from: ctx.accounts.wallet_token_account.to_account_info(),
-            to: ctx.accounts.protocol_treasury.to_account_info(),
-            authority: wallet.to_account_info(),
-        };
-        
-        let cpi_ctx = CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            transfer_protocol,
-            signer_seeds,
-        );
-        
-        token::transfer(cpi_ctx, protocol_fee)?;
+    if let Some(keeper_token_account) = ctx.accounts.keeper_token_account.as_ref() {
+        transfer_from_wallet(
+            wallet,
+            &ctx.accounts.wallet_token_account,
+            keeper_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+            split.keeper_reward,
+        )?;
     }
 
+    unwrap_sol_payout(
+        ctx.accounts.sol_unwrap_account.as_ref(),
+        ctx.bumps.sol_unwrap_account,
+        ctx.accounts.merchant.as_ref(),
+        ctx.accounts.payer.as_ref(),
+        ctx.accounts.system_program.as_ref(),
+        &ctx.accounts.token_program,
+    )?;
+
     // Update state
-    subscription.last_payment_timestamp = current_time;
-    subscription.total_paid = subscription.total_paid
-        .checked_add(total_charge)
-        .ok_or(ErrorCodes::MathOverflow)?;
-    subscription.payment_count = subscription.payment_count
-        .checked_add(1)
-        .ok_or(ErrorCodes::MathOverflow)?;
-
-    wallet.total_spent = wallet.total_spent
-        .checked_add(total_charge)
-        .ok_or(ErrorCodes::MathOverflow)?;
-
-    emit!(PaymentExecuted {
-        subscription_pda: subscription.key(),
-        wallet_pda: wallet.key(),
-        user: subscription.user,
-        merchant: subscription.merchant,
-        amount: total_charge,
-        protocol_fee: protocol_fee,
-        merchant_received: merchant_receives,
-        payment_number: subscription.payment_count,
-    });
-
-    Ok(())
+    record_charge(subscription, wallet, merchant_plan, &charge, &split, payee_payouts, current_time)
 }
@@ -0,0 +1,239 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenInterface, TokenAccount};
+use crate::{
+    SubscriptionState, SubscriptionWallet, MerchantPlan, ProtocolConfig,
+    PaymentSkipped, PaymentSkipReason, ErrorCodes
+};
+use crate::utils::{
+    prepare_charge, PreparedCharge, split_charge, record_charge, transfer_payment, PaymentAccounts,
+    transfer_from_wallet, payment_cost, quote_for_plan, resume_lapsed_pause,
+    merchant_payout_account, unwrap_sol_payout
+};
+
+/// Fixed accounts per subscription in `remaining_accounts`:
+/// subscription state, subscription wallet, wallet token account, merchant plan.
+/// Each group is followed by one payee token account per revenue split of its plan.
+pub const BATCH_GROUP_SIZE: usize = 4;
+
+#[derive(Accounts)]
+pub struct ExecutePaymentsBatch<'info> {
+    // Every subscription in the batch must be on one of this merchant's plans;
+    // mutable so native SOL payouts can be paid to it
+    #[account(mut)]
+    pub merchant: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        constraint = merchant_token_account.owner == merchant.key() @ ErrorCodes::InvalidMerchantAccount
+    )]
+    pub merchant_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        constraint = protocol_treasury.owner == protocol_config.treasury @ ErrorCodes::InvalidTreasuryAccount
+    )]
+    pub protocol_treasury: InterfaceAccount<'info, TokenAccount>,
+
+    // Optional keeper reward destination (one reward per charged subscription)
+    #[account(
+        mut,
+        token::mint = mint,
+    )]
+    pub keeper_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    // Settlement mint; every plan in the batch must settle in it
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Pyth price update, validated against each oracle-priced plan's feed
+    /// for `mint` (required when the batch includes oracle-priced plans)
+    pub price_feed: Option<UncheckedAccount<'info>>,
+
+    // Native SOL payout (plans with unwrap_sol): the merchant's share passes through
+    // this temporary wSOL account and is paid to `merchant` as SOL
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"sol_unwrap", merchant.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = sol_unwrap_account,
+        token::token_program = token_program,
+    )]
+    pub sol_unwrap_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    // Funds the temporary account's rent, which is returned in the same instruction
+    #[account(mut)]
+    pub payer: Option<Signer<'info>>,
+
+    pub system_program: Option<Program<'info, System>>,
+}
+
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ExecutePaymentsBatch<'info>>,
+) -> Result<()> {
+    require!(
+        !ctx.remaining_accounts.is_empty(),
+        ErrorCodes::InvalidBatchAccounts
+    );
+
+    let protocol_config = &ctx.accounts.protocol_config;
+    let merchant_key = ctx.accounts.merchant.key();
+    let mint_key = ctx.accounts.mint.key();
+    let keeper = ctx.accounts.keeper_token_account.as_ref().map(|account| account.owner);
+    let current_time = Clock::get()?.unix_timestamp;
+
+    let mut remaining = ctx.remaining_accounts;
+    while !remaining.is_empty() {
+        require!(
+            remaining.len() >= BATCH_GROUP_SIZE,
+            ErrorCodes::InvalidBatchAccounts
+        );
+        let (group, rest) = remaining.split_at(BATCH_GROUP_SIZE);
+
+        let merchant_plan: Account<'info, MerchantPlan> = Account::try_from(&group[3])?;
+        let payee_count = merchant_plan.revenue_splits.len();
+        require!(
+            rest.len() >= payee_count,
+            ErrorCodes::InvalidBatchAccounts
+        );
+        let (payee_token_accounts, rest) = rest.split_at(payee_count);
+        remaining = rest;
+
+        require!(
+            group[..3].iter().chain(payee_token_accounts).all(|account| account.is_writable),
+            ErrorCodes::InvalidBatchAccounts
+        );
+
+        let mut subscription: Account<'info, SubscriptionState> = Account::try_from(&group[0])?;
+        let mut wallet: Account<'info, SubscriptionWallet> = Account::try_from(&group[1])?;
+        let wallet_token_account: InterfaceAccount<'info, TokenAccount> = InterfaceAccount::try_from(&group[2])?;
+
+        // A malformed group aborts the batch; only billing outcomes are skipped
+        require!(
+            merchant_plan.merchant == merchant_key
+                && merchant_plan.settles_in(&mint_key)
+                && subscription.merchant_plan == merchant_plan.key()
+                && wallet.key() == subscription.subscription_wallet
+                && subscription.mint == mint_key
+                && wallet_token_account.mint == subscription.mint
+                && wallet_token_account.owner == wallet.key(),
+            ErrorCodes::InvalidBatchAccounts
+        );
+
+        // Plans price differently, so each oracle-priced group takes its own quote
+        let quote = quote_for_plan(
+            &merchant_plan,
+            ctx.accounts.price_feed.as_ref().map(|account| account.as_ref()),
+            Some(&ctx.accounts.mint),
+            current_time,
+        )?;
+        let merchant_plan = &merchant_plan;
+
+        resume_lapsed_pause(&mut subscription, current_time)?;
+
+        let skip_reason = if !subscription.is_active || !merchant_plan.is_active {
+            Some(PaymentSkipReason::Inactive)
+        } else if subscription.is_paused(current_time) {
+            Some(PaymentSkipReason::Paused)
+        } else if !subscription.is_payment_due(current_time) {
+            Some(PaymentSkipReason::NotDue)
+        } else if subscription.billable_periods(current_time, merchant_plan.billing_mode) == 0 {
+            Some(PaymentSkipReason::CancellationScheduled)
+        } else {
+            match prepare_charge(&mut subscription, &mut wallet, merchant_plan, quote.as_ref(), current_time)? {
+                PreparedCharge::PriceActionRequired => Some(PaymentSkipReason::PriceActionRequired),
+                PreparedCharge::ChargeLimitExceeded => Some(PaymentSkipReason::ChargeLimitExceeded),
+                PreparedCharge::PriceSlippageExceeded => Some(PaymentSkipReason::PriceSlippageExceeded),
+                PreparedCharge::Expired => Some(PaymentSkipReason::Expired),
+                PreparedCharge::Due(charge) => {
+                    let split = split_charge(charge.amount, protocol_config, keeper)?;
+                    let cost = payment_cost(
+                        merchant_plan,
+                        &ctx.accounts.mint,
+                        split.merchant_receives,
+                        &[split.treasury_receives, split.keeper_reward],
+                    )?;
+
+                    // Yield shares are not redeemed in a batch; use execute_payment_from_wallet
+                    if wallet_token_account.amount < cost {
+                        Some(PaymentSkipReason::InsufficientFunds)
+                    } else {
+                        let payee_payouts = transfer_payment(
+                            &wallet,
+                            &PaymentAccounts {
+                                wallet_token_account: &wallet_token_account,
+                                merchant_token_account: merchant_payout_account(
+                                    merchant_plan,
+                                    &ctx.accounts.merchant_token_account,
+                                    ctx.accounts.sol_unwrap_account.as_ref(),
+                                )?,
+                                protocol_treasury: &ctx.accounts.protocol_treasury,
+                                payee_token_accounts,
+                                mint: &ctx.accounts.mint,
+                                token_program: &ctx.accounts.token_program,
+                            },
+                            merchant_plan,
+                            split.merchant_receives,
+                            split.treasury_receives,
+                        )?;
+
+                        if let Some(keeper_token_account) = ctx.accounts.keeper_token_account.as_ref() {
+                            transfer_from_wallet(
+                                &wallet,
+                                &wallet_token_account,
+                                keeper_token_account,
+                                &ctx.accounts.mint,
+                                &ctx.accounts.token_program,
+                                split.keeper_reward,
+                            )?;
+                        }
+
+                        record_charge(
+                            &mut subscription,
+                            &mut wallet,
+                            merchant_plan,
+                            &charge,
+                            &split,
+                            payee_payouts,
+                            current_time,
+                        )?;
+                        None
+                    }
+                }
+            }
+        };
+
+        if let Some(reason) = skip_reason {
+            emit!(PaymentSkipped {
+                subscription_pda: subscription.key(),
+                user: subscription.user,
+                merchant: subscription.merchant,
+                reason,
+            });
+        }
+
+        // Persist now so a repeated group later in the batch sees the update
+        subscription.exit(&crate::ID)?;
+        wallet.exit(&crate::ID)?;
+    }
+
+    // Every SOL payout in the batch is unwrapped to the merchant at once
+    unwrap_sol_payout(
+        ctx.accounts.sol_unwrap_account.as_ref(),
+        ctx.bumps.sol_unwrap_account,
+        Some(&ctx.accounts.merchant),
+        ctx.accounts.payer.as_ref(),
+        ctx.accounts.system_program.as_ref(),
+        &ctx.accounts.token_program,
+    )
+}
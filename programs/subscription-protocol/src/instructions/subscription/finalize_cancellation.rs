@@ -0,0 +1,75 @@
+use anchor_lang::prelude::*;
+use crate::{
+    SubscriptionState, SubscriptionWallet, MerchantPlan,
+    SubscriptionCancelled, ErrorCodes
+};
+
+#[derive(Accounts)]
+pub struct FinalizeCancellation<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            subscription_state.user.as_ref(),
+            subscription_state.merchant_plan.as_ref(),
+            &subscription_state.subscription_index.to_le_bytes()
+        ],
+        bump = subscription_state.bump,
+        has_one = user,
+        close = user
+    )]
+    pub subscription_state: Account<'info, SubscriptionState>,
+
+    #[account(
+        mut,
+        constraint = subscription_wallet.key() == subscription_state.subscription_wallet
+    )]
+    pub subscription_wallet: Account<'info, SubscriptionWallet>,
+
+    #[account(
+        mut,
+        constraint = merchant_plan.key() == subscription_state.merchant_plan @ ErrorCodes::InvalidMerchantPlan,
+    )]
+    pub merchant_plan: Account<'info, MerchantPlan>,
+
+    /// CHECK: Subscriber receiving the rent; verified by has_one
+    #[account(mut)]
+    pub user: AccountInfo<'info>,
+}
+
+pub fn handler(ctx: Context<FinalizeCancellation>) -> Result<()> {
+    let subscription = &ctx.accounts.subscription_state;
+    let wallet = &mut ctx.accounts.subscription_wallet;
+    let merchant_plan = &mut ctx.accounts.merchant_plan;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    require!(
+        subscription.has_scheduled_cancellation(),
+        ErrorCodes::NoCancellationScheduled
+    );
+    require!(
+        current_time >= subscription.scheduled_cancel_at,
+        ErrorCodes::CancellationNotDue
+    );
+
+    // In-arrears plans still owe the last period unless dunning gave up on it
+    require!(
+        subscription.is_suspended
+            || subscription.billable_periods(current_time, merchant_plan.billing_mode) == 0,
+        ErrorCodes::FinalPaymentOutstanding
+    );
+
+    wallet.total_subscriptions = wallet.total_subscriptions.saturating_sub(1);
+    wallet.release(subscription.committed_amount);
+    merchant_plan.total_subscribers = merchant_plan.total_subscribers.saturating_sub(1);
+
+    emit!(SubscriptionCancelled {
+        subscription_pda: subscription.key(),
+        wallet_pda: wallet.key(),
+        user: subscription.user,
+        merchant: subscription.merchant,
+        payments_made: subscription.payment_count,
+    });
+
+    Ok(())
+}
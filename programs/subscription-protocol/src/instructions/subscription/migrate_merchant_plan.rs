@@ -0,0 +1,110 @@
+use anchor_lang::prelude::*;
+use crate::{
+    MerchantPlan, LegacyMerchantPlan, BillingMode, MissedPeriodPolicy, DunningConfig,
+    RefundPolicy, PausePolicy, MeteringConfig, MerchantPlanMigrated, ErrorCodes
+};
+use crate::utils::grow_account;
+
+#[derive(Accounts)]
+pub struct MigrateMerchantPlan<'info> {
+    /// CHECK: Plan created before fields were appended after `bump`; the
+    /// legacy layout is deserialized and its address checked in the handler
+    #[account(mut, owner = crate::ID)]
+    pub merchant_plan: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<MigrateMerchantPlan>) -> Result<()> {
+    let plan_info = ctx.accounts.merchant_plan.to_account_info();
+    let full_len = 8 + MerchantPlan::INIT_SPACE;
+    require!(plan_info.data_len() < full_len, ErrorCodes::PlanAlreadyMigrated);
+
+    let legacy = {
+        let data = plan_info.try_borrow_data()?;
+        require!(
+            data.len() >= 8 && data[..8] == *MerchantPlan::DISCRIMINATOR,
+            ErrorCodes::InvalidMerchantPlan
+        );
+        LegacyMerchantPlan::deserialize(&mut &data[8..])?
+    };
+
+    require!(
+        legacy.merchant == ctx.accounts.merchant.key(),
+        ErrorCodes::UnauthorizedMerchant
+    );
+    let plan_pda = Pubkey::create_program_address(
+        &[
+            b"merchant_plan",
+            legacy.merchant.as_ref(),
+            legacy.mint.as_ref(),
+            legacy.plan_id.as_bytes(),
+            &[legacy.bump],
+        ],
+        &crate::ID,
+    )
+    .map_err(|_| ErrorCodes::InvalidMerchantPlan)?;
+    require!(plan_pda == plan_info.key(), ErrorCodes::InvalidMerchantPlan);
+
+    // Settings the first deployment had implicitly: charge one interval after
+    // subscribing, settle only the latest missed period, no trials, pauses,
+    // refunds or metering. The merchant can change them with update_merchant_plan.
+    let plan = MerchantPlan {
+        merchant: legacy.merchant,
+        mint: legacy.mint,
+        plan_id: legacy.plan_id,
+        plan_name: legacy.plan_name,
+        fee_amount: legacy.fee_amount,
+        payment_interval: legacy.payment_interval,
+        is_active: legacy.is_active,
+        total_subscribers: legacy.total_subscribers,
+        bump: legacy.bump,
+        price_version: 0,
+        pending_fee_amount: 0,
+        price_effective_at: 0,
+        price_cutoff_at: 0,
+        trial_period_seconds: 0,
+        billing_mode: BillingMode::InArrears,
+        missed_period_policy: MissedPeriodPolicy::ChargeLatest,
+        dunning: DunningConfig {
+            grace_period_seconds: legacy.payment_interval,
+            retry_interval_seconds: 0,
+            max_retries: u8::MAX,
+        },
+        refund_policy: RefundPolicy {
+            prorate_on_cancel: false,
+            reverse_protocol_fee: false,
+        },
+        pause_policy: PausePolicy {
+            allow_pause: false,
+            max_pause_seconds: 0,
+        },
+        metering: MeteringConfig {
+            unit_price: 0,
+            included_units: 0,
+            max_units: 0,
+        },
+        revenue_splits: Vec::new(),
+        unwrap_sol: false,
+        oracle_pricing: None,
+    };
+
+    grow_account(
+        &plan_info,
+        &ctx.accounts.merchant.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        full_len,
+    )?;
+    plan.try_serialize(&mut &mut plan_info.try_borrow_mut_data()?[..])?;
+
+    emit!(MerchantPlanMigrated {
+        plan_pda,
+        merchant: plan.merchant,
+        plan_id: plan.plan_id,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,171 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use crate::{
+    SubscriptionState, LegacySubscriptionState, SubscriptionWallet, MerchantPlan, MeteringConfig,
+    SubscriptionMigrated, ErrorCodes
+};
+use crate::utils::calculate_subscription_commitment;
+
+#[derive(Accounts)]
+#[instruction(subscription_index: u16)]
+pub struct MigrateSubscription<'info> {
+    /// CHECK: Subscription under the legacy `[user, merchant, mint]` seeds;
+    /// address is verified here and contents are deserialized in the handler
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            user.key().as_ref(),
+            merchant_plan.merchant.as_ref(),
+            merchant_plan.mint.as_ref()
+        ],
+        bump,
+        owner = crate::ID,
+    )]
+    pub legacy_subscription: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + SubscriptionState::INIT_SPACE,
+        seeds = [
+            b"subscription",
+            user.key().as_ref(),
+            merchant_plan.key().as_ref(),
+            &subscription_index.to_le_bytes()
+        ],
+        bump
+    )]
+    pub subscription_state: Account<'info, SubscriptionState>,
+
+    pub merchant_plan: Account<'info, MerchantPlan>,
+
+    // The wallet the legacy subscription pays from (index 0, migrated first)
+    #[account(
+        mut,
+        seeds = [
+            b"subscription_wallet",
+            user.key().as_ref(),
+            merchant_plan.mint.as_ref(),
+            &SubscriptionWallet::index_seed(0)
+        ],
+        bump = subscription_wallet.bump,
+    )]
+    pub subscription_wallet: Account<'info, SubscriptionWallet>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<MigrateSubscription>, subscription_index: u16) -> Result<()> {
+    let legacy_info = ctx.accounts.legacy_subscription.to_account_info();
+
+    let legacy = {
+        let data = legacy_info.try_borrow_data()?;
+        require!(
+            data.len() >= 8 && data[..8] == *SubscriptionState::DISCRIMINATOR,
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+        LegacySubscriptionState::deserialize(&mut &data[8..])?
+    };
+
+    require!(
+        legacy.user == ctx.accounts.user.key(),
+        ErrorCodes::UnauthorizedSubscriber
+    );
+    require!(
+        legacy.merchant_plan == ctx.accounts.merchant_plan.key(),
+        ErrorCodes::InvalidMerchantPlan
+    );
+    require!(
+        legacy.subscription_wallet == ctx.accounts.subscription_wallet.key(),
+        ErrorCodes::SubscriptionWalletMismatch
+    );
+
+    // Legacy subscriptions reserved nothing in the wallet, so reserve the
+    // buffer a new subscription at this fee would
+    let committed_amount = if legacy.is_active {
+        let commitment = calculate_subscription_commitment(legacy.fee_amount)?;
+        ctx.accounts.subscription_wallet.commit(commitment)?;
+        commitment
+    } else {
+        0
+    };
+
+    // Appended fields start where a fresh single-seat subscription would:
+    // no trial, dunning, pause, limits, coupon or price change in flight
+    ctx.accounts.subscription_state.set_inner(SubscriptionState {
+        user: legacy.user,
+        subscription_wallet: legacy.subscription_wallet,
+        merchant: legacy.merchant,
+        mint: legacy.mint,
+        merchant_plan: legacy.merchant_plan,
+        fee_amount: legacy.fee_amount,
+        payment_interval: legacy.payment_interval,
+        last_payment_timestamp: legacy.last_payment_timestamp,
+        total_paid: legacy.total_paid,
+        payment_count: legacy.payment_count,
+        is_active: legacy.is_active,
+        session_token: legacy.session_token,
+        bump: ctx.bumps.subscription_state,
+        committed_amount,
+        subscription_index,
+        trial_ends_at: 0,
+        is_past_due: false,
+        past_due_since: 0,
+        failed_payment_attempts: 0,
+        last_failed_attempt_at: 0,
+        is_suspended: false,
+        price_version: 0,
+        accepted_price_version: 0,
+        accepted_fee_amount: 0,
+        price_action_required: false,
+        cancel_at_period_end: false,
+        scheduled_cancel_at: 0,
+        proration_credit: 0,
+        total_refunded: 0,
+        paused_at: 0,
+        paused_until: 0,
+        max_amount_per_charge: 0,
+        max_total_spend: 0,
+        max_payments: 0,
+        expired_at: 0,
+        period_usage: 0,
+        quantity: 1,
+        coupon: None,
+        coupon_discount: None,
+        coupon_periods_remaining: 0,
+        max_price_slippage_bps: 0,
+        accepted_effective_at: 0,
+        metering: MeteringConfig {
+            unit_price: 0,
+            included_units: 0,
+            max_units: 0,
+        },
+        // Legacy subscriptions were always charged their flat fee
+        last_charge_amount: if legacy.payment_count > 0 { legacy.fee_amount } else { 0 },
+        last_charge_refunded: 0,
+    });
+
+    // Close the legacy account and return its rent to the subscriber
+    let user_info = ctx.accounts.user.to_account_info();
+    let legacy_lamports = legacy_info.lamports();
+    **user_info.try_borrow_mut_lamports()? = user_info.lamports()
+        .checked_add(legacy_lamports)
+        .ok_or(ErrorCodes::MathOverflow)?;
+    **legacy_info.try_borrow_mut_lamports()? = 0;
+    legacy_info.assign(&system_program::ID);
+    legacy_info.resize(0)?;
+
+    emit!(SubscriptionMigrated {
+        legacy_pda: legacy_info.key(),
+        subscription_pda: ctx.accounts.subscription_state.key(),
+        user: ctx.accounts.user.key(),
+        plan_pda: ctx.accounts.merchant_plan.key(),
+        subscription_index,
+    });
+
+    Ok(())
+}
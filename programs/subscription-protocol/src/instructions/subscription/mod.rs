@@ -1,9 +1,57 @@
+#![allow(ambiguous_glob_reexports)]
+
 pub mod register_merchant;
+pub mod update_merchant_plan;
+pub mod pause_plan;
+pub mod resume_plan;
+pub mod close_plan;
+pub mod migrate_merchant_plan;
+pub mod publish_price_change;
+pub mod create_coupon;
 pub mod subscribe;
+pub mod migrate_subscription;
+pub mod accept_price_change;
+pub mod change_plan;
+pub mod apply_coupon;
+pub mod update_quantity;
+pub mod pause_subscription;
+pub mod resume_subscription;
+pub mod set_spending_limits;
+pub mod report_usage;
 pub mod execute_payment;
+pub mod execute_payments_batch;
+pub mod record_failed_payment;
+pub mod refund_payment;
 pub mod cancel_subscription;
+pub mod terminate_subscription;
+pub mod schedule_cancellation;
+pub mod undo_cancellation;
+pub mod finalize_cancellation;
 
 pub use register_merchant::*;
+pub use update_merchant_plan::*;
+pub use pause_plan::*;
+pub use resume_plan::*;
+pub use close_plan::*;
+pub use migrate_merchant_plan::*;
+pub use publish_price_change::*;
+pub use create_coupon::*;
 pub use subscribe::*;
+pub use migrate_subscription::*;
+pub use accept_price_change::*;
+pub use change_plan::*;
+pub use apply_coupon::*;
+pub use update_quantity::*;
+pub use pause_subscription::*;
+pub use resume_subscription::*;
+pub use set_spending_limits::*;
+pub use report_usage::*;
 pub use execute_payment::*;
-pub use cancel_subscription::*;
\ No newline at end of file
+pub use execute_payments_batch::*;
+pub use record_failed_payment::*;
+pub use refund_payment::*;
+pub use cancel_subscription::*;
+pub use terminate_subscription::*;
+pub use schedule_cancellation::*;
+pub use undo_cancellation::*;
+pub use finalize_cancellation::*;
@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+use crate::{SubscriptionState, MerchantPlan, SubscriptionPaused, ErrorCodes};
+use crate::utils::resume_lapsed_pause;
+
+#[derive(Accounts)]
+pub struct PauseSubscription<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            subscription_state.user.as_ref(),
+            subscription_state.merchant_plan.as_ref(),
+            &subscription_state.subscription_index.to_le_bytes()
+        ],
+        bump = subscription_state.bump,
+        has_one = user @ ErrorCodes::UnauthorizedSubscriber,
+        constraint = subscription_state.is_active @ ErrorCodes::SubscriptionInactive,
+        constraint = !subscription_state.is_suspended @ ErrorCodes::SubscriptionSuspended,
+        constraint = !subscription_state.is_paused(Clock::get()?.unix_timestamp) @ ErrorCodes::SubscriptionPaused,
+    )]
+    pub subscription_state: Account<'info, SubscriptionState>,
+
+    #[account(
+        constraint = merchant_plan.key() == subscription_state.merchant_plan @ ErrorCodes::InvalidMerchantPlan,
+    )]
+    pub merchant_plan: Account<'info, MerchantPlan>,
+
+    pub user: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<PauseSubscription>, pause_seconds: i64) -> Result<()> {
+    let subscription = &mut ctx.accounts.subscription_state;
+    let pause_policy = ctx.accounts.merchant_plan.pause_policy;
+    let current_time = Clock::get()?.unix_timestamp;
+    resume_lapsed_pause(subscription, current_time)?;
+
+    require!(pause_policy.allow_pause, ErrorCodes::PauseNotAllowed);
+    require!(
+        pause_seconds > 0 && pause_seconds <= pause_policy.max_pause_seconds,
+        ErrorCodes::InvalidPauseLength
+    );
+    require!(
+        !subscription.is_past_due && !subscription.is_payment_due(current_time),
+        ErrorCodes::PaymentOutstanding
+    );
+
+    subscription.paused_at = current_time;
+    subscription.paused_until = current_time
+        .checked_add(pause_seconds)
+        .ok_or(ErrorCodes::MathOverflow)?;
+
+    emit!(SubscriptionPaused {
+        subscription_pda: subscription.key(),
+        user: subscription.user,
+        merchant: subscription.merchant,
+        paused_until: subscription.paused_until,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use anchor_lang::prelude::*;
+use crate::{MerchantPlan, PriceChangePublished, ErrorCodes};
+
+#[derive(Accounts)]
+pub struct PublishPriceChange<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"merchant_plan",
+            merchant_plan.merchant.as_ref(),
+            merchant_plan.mint.as_ref(),
+            merchant_plan.plan_id.as_bytes()
+        ],
+        bump = merchant_plan.bump,
+        has_one = merchant @ ErrorCodes::UnauthorizedMerchant
+    )]
+    pub merchant_plan: Account<'info, MerchantPlan>,
+
+    pub merchant: Signer<'info>,
+}
+
+pub fn handler(
+    ctx: Context<PublishPriceChange>,
+    new_fee_amount: u64,
+    effective_at: i64,
+    cutoff_at: i64,
+) -> Result<()> {
+    let merchant_plan = &mut ctx.accounts.merchant_plan;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    require!(new_fee_amount > 0, ErrorCodes::InvalidFeeAmount);
+    require!(
+        effective_at >= current_time && cutoff_at >= effective_at,
+        ErrorCodes::InvalidPriceChangeSchedule
+    );
+    require!(
+        !merchant_plan.has_pending_price_change(current_time),
+        ErrorCodes::PriceChangePending
+    );
+
+    // Fold the previous version into the base fee before publishing the next one
+    let old_fee_amount = merchant_plan.current_fee(current_time);
+    merchant_plan.fee_amount = old_fee_amount;
+
+    merchant_plan.price_version = merchant_plan.price_version
+        .checked_add(1)
+        .ok_or(ErrorCodes::MathOverflow)?;
+    merchant_plan.pending_fee_amount = new_fee_amount;
+    merchant_plan.price_effective_at = effective_at;
+    merchant_plan.price_cutoff_at = cutoff_at;
+
+    emit!(PriceChangePublished {
+        plan_pda: merchant_plan.key(),
+        price_version: merchant_plan.price_version,
+        old_fee_amount,
+        new_fee_amount,
+        effective_at,
+        cutoff_at,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,164 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use crate::{
+    SubscriptionState, SubscriptionWallet, MerchantPlan, YieldVault,
+    PaymentFailed, SubscriptionPastDue, SubscriptionSuspended, ErrorCodes
+};
+use crate::utils::{
+    wallet_yield_value, price_periods, quote_for_plan, settlement_amount, resume_lapsed_pause
+};
+
+#[derive(Accounts)]
+pub struct RecordFailedPayment<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            subscription_state.user.as_ref(),
+            subscription_state.merchant_plan.as_ref(),
+            &subscription_state.subscription_index.to_le_bytes()
+        ],
+        bump = subscription_state.bump,
+        constraint = subscription_state.is_active @ ErrorCodes::SubscriptionInactive,
+        constraint = !subscription_state.is_suspended @ ErrorCodes::SubscriptionSuspended,
+        constraint = !subscription_state.is_paused(Clock::get()?.unix_timestamp) @ ErrorCodes::SubscriptionPaused,
+    )]
+    pub subscription_state: Account<'info, SubscriptionState>,
+
+    #[account(
+        seeds = [
+            b"subscription_wallet",
+            subscription_wallet.owner.as_ref(),
+            subscription_wallet.mint.as_ref(),
+            &SubscriptionWallet::index_seed(subscription_wallet.wallet_index)
+        ],
+        bump = subscription_wallet.bump,
+        constraint = subscription_wallet.key() == subscription_state.subscription_wallet
+    )]
+    pub subscription_wallet: Account<'info, SubscriptionWallet>,
+
+    #[account(
+        constraint = merchant_plan.key() == subscription_state.merchant_plan @ ErrorCodes::InvalidMerchantPlan,
+    )]
+    pub merchant_plan: Account<'info, MerchantPlan>,
+
+    #[account(
+        token::mint = subscription_wallet.mint,
+        token::authority = subscription_wallet
+    )]
+    pub wallet_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    // Optional yield vault accounts (required when the wallet holds yield shares)
+    #[account(
+        seeds = [b"yield_vault", subscription_wallet.mint.as_ref()],
+        bump = yield_vault.bump,
+    )]
+    pub yield_vault: Option<Account<'info, YieldVault>>,
+
+    pub vault_buffer: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub jupiter_ftoken_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Jupiter Lend lending account
+    pub jupiter_lending: Option<AccountInfo<'info>>,
+
+    // Oracle-priced plans: converts the amount due into tokens
+    #[account(address = subscription_state.mint)]
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK: Pyth price update, validated against the plan's oracle settings
+    pub price_feed: Option<UncheckedAccount<'info>>,
+}
+
+pub fn handler(ctx: Context<RecordFailedPayment>) -> Result<()> {
+    let subscription = &mut ctx.accounts.subscription_state;
+    let merchant_plan = &ctx.accounts.merchant_plan;
+    let wallet = &ctx.accounts.subscription_wallet;
+    let dunning = merchant_plan.dunning;
+    let current_time = Clock::get()?.unix_timestamp;
+    resume_lapsed_pause(subscription, current_time)?;
+
+    require!(
+        subscription.is_payment_due(current_time),
+        ErrorCodes::PaymentTooEarly
+    );
+
+    if subscription.failed_payment_attempts > 0 {
+        let next_retry_at = subscription.last_failed_attempt_at
+            .checked_add(dunning.retry_interval_seconds)
+            .ok_or(ErrorCodes::MathOverflow)?;
+        require!(current_time >= next_retry_at, ErrorCodes::RetryTooEarly);
+    }
+
+    // Amount execute_payment_from_wallet would charge right now
+    let periods_due = subscription.billable_periods(current_time, merchant_plan.billing_mode);
+    require!(periods_due > 0, ErrorCodes::ChargeAfterCancellation);
+    let (periods_charged, _) = merchant_plan.missed_period_policy.resolve(periods_due);
+    let quote = quote_for_plan(
+        merchant_plan,
+        ctx.accounts.price_feed.as_ref().map(|account| account.as_ref()),
+        ctx.accounts.mint.as_ref(),
+        current_time,
+    )?;
+    let amount_due = settlement_amount(
+        quote.as_ref(),
+        price_periods(subscription, periods_charged)?.gross_amount,
+    )?
+    .saturating_sub(subscription.proration_credit);
+
+    // Keepers must prove the wallet can't cover the charge, valuing the whole
+    // vault position (buffer and Jupiter Lend) so yield can't be left out
+    let yield_value = wallet_yield_value(
+        wallet,
+        ctx.accounts.yield_vault.as_ref(),
+        ctx.accounts.vault_buffer.as_ref(),
+        ctx.accounts.jupiter_ftoken_account.as_ref(),
+        ctx.accounts.jupiter_lending.as_ref(),
+    )?;
+
+    let available = ctx.accounts.wallet_token_account.amount
+        .checked_add(yield_value)
+        .ok_or(ErrorCodes::MathOverflow)?;
+    require!(available < amount_due, ErrorCodes::PaymentCoverable);
+
+    if !subscription.is_past_due {
+        subscription.is_past_due = true;
+        subscription.past_due_since = subscription.next_payment_due();
+
+        emit!(SubscriptionPastDue {
+            subscription_pda: subscription.key(),
+            user: subscription.user,
+            merchant: subscription.merchant,
+            periods_outstanding: periods_due as u32,
+        });
+    }
+
+    subscription.failed_payment_attempts = subscription.failed_payment_attempts.saturating_add(1);
+    subscription.last_failed_attempt_at = current_time;
+
+    emit!(PaymentFailed {
+        subscription_pda: subscription.key(),
+        wallet_pda: wallet.key(),
+        user: subscription.user,
+        merchant: subscription.merchant,
+        amount_due,
+        attempt: subscription.failed_payment_attempts,
+        next_retry_at: current_time.saturating_add(dunning.retry_interval_seconds),
+    });
+
+    // Suspend once retries are exhausted or the grace period has run out
+    let grace_ends_at = subscription.past_due_since.saturating_add(dunning.grace_period_seconds);
+    if subscription.failed_payment_attempts > dunning.max_retries || current_time >= grace_ends_at {
+        subscription.is_suspended = true;
+
+        emit!(SubscriptionSuspended {
+            subscription_pda: subscription.key(),
+            user: subscription.user,
+            merchant: subscription.merchant,
+            failed_attempts: subscription.failed_payment_attempts,
+            past_due_since: subscription.past_due_since,
+        });
+    }
+
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenInterface, TokenAccount, TransferChecked};
+use crate::{
+    SubscriptionState, MerchantPlan, ProtocolConfig, RefundIssued, ErrorCodes
+};
+use crate::utils::{calculate_refund, reverse_protocol_fee};
+
+#[derive(Accounts)]
+pub struct RefundPayment<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            subscription_state.user.as_ref(),
+            subscription_state.merchant_plan.as_ref(),
+            &subscription_state.subscription_index.to_le_bytes()
+        ],
+        bump = subscription_state.bump,
+        has_one = merchant @ ErrorCodes::UnauthorizedMerchant,
+    )]
+    pub subscription_state: Account<'info, SubscriptionState>,
+
+    #[account(
+        constraint = merchant_plan.key() == subscription_state.merchant_plan @ ErrorCodes::InvalidMerchantPlan,
+    )]
+    pub merchant_plan: Account<'info, MerchantPlan>,
+
+    #[account(
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    pub merchant: Signer<'info>,
+
+    #[account(
+        mut,
+        token::mint = subscription_state.mint,
+        token::authority = merchant
+    )]
+    pub merchant_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = subscription_state.mint,
+        token::authority = subscription_state.subscription_wallet
+    )]
+    pub wallet_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = subscription_state.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    // Protocol fee reversal (required when the plan's refund policy reverses the fee)
+    #[account(
+        mut,
+        token::mint = subscription_state.mint,
+        constraint = protocol_treasury.owner == protocol_config.treasury @ ErrorCodes::InvalidTreasuryAccount
+    )]
+    pub protocol_treasury: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = protocol_config.authority @ ErrorCodes::UnauthorizedProtocolUpdate)]
+    pub protocol_authority: Option<Signer<'info>>,
+}
+
+// Refunds apply to the most recent payment, up to what it charged
+pub fn handler(
+    ctx: Context<RefundPayment>,
+    amount: u64,
+) -> Result<()> {
+    let subscription = &mut ctx.accounts.subscription_state;
+
+    require!(amount > 0, ErrorCodes::InvalidRefundAmount);
+    require!(
+        amount <= subscription.last_charge_refundable(),
+        ErrorCodes::RefundExceedsPaid
+    );
+
+    let (refund, protocol_fee_reversed) = calculate_refund(
+        amount,
+        &ctx.accounts.protocol_config,
+        &ctx.accounts.merchant_plan.refund_policy,
+    )?;
+
+    let cpi_accounts = TransferChecked {
+        from: ctx.accounts.merchant_token_account.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        to: ctx.accounts.wallet_token_account.to_account_info(),
+        authority: ctx.accounts.merchant.to_account_info(),
+    };
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    token_interface::transfer_checked(
+        CpiContext::new(cpi_program, cpi_accounts),
+        refund,
+        ctx.accounts.mint.decimals,
+    )?;
+
+    reverse_protocol_fee(
+        ctx.accounts.protocol_treasury.as_ref(),
+        ctx.accounts.protocol_authority.as_ref(),
+        &ctx.accounts.wallet_token_account,
+        &ctx.accounts.mint,
+        &ctx.accounts.token_program,
+        protocol_fee_reversed,
+    )?;
+
+    subscription.total_refunded = subscription.total_refunded
+        .checked_add(amount)
+        .ok_or(ErrorCodes::MathOverflow)?;
+    subscription.last_charge_refunded = subscription.last_charge_refunded
+        .checked_add(amount)
+        .ok_or(ErrorCodes::MathOverflow)?;
+
+    emit!(RefundIssued {
+        subscription_pda: subscription.key(),
+        user: subscription.user,
+        merchant: subscription.merchant,
+        payment_number: subscription.payment_count,
+        amount: refund + protocol_fee_reversed,
+        protocol_fee_reversed,
+        prorated: false,
+    });
+
+    Ok(())
+}
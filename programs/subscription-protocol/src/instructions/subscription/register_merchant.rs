@@ -1,6 +1,9 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::Mint;
-use crate::{MerchantPlan, MerchantPlanRegistered, ErrorCodes};
+use anchor_spl::token_interface::Mint;
+use crate::{
+    MerchantPlan, PlanConfig, BillingMode, RevenueSplit, MerchantPlanRegistered, ErrorCodes
+};
+use crate::utils::{has_transfer_hook, is_native_mint};
 
 #[derive(Accounts)]
 #[instruction(plan_id: String)]
@@ -22,21 +25,61 @@ pub struct RegisterMerchant<'info> {
     #[account(mut)]
     pub merchant: Signer<'info>,
 
-    pub mint: Account<'info, Mint>,
+    pub mint: InterfaceAccount<'info, Mint>,
     pub system_program: Program<'info, System>,
 }
 
 pub fn handler(
     ctx: Context<RegisterMerchant>,
     plan_id: String,
-    plan_name: String,
-    fee_amount: u64,
-    payment_interval_seconds: i64,
+    config: PlanConfig,
 ) -> Result<()> {
+    let PlanConfig {
+        plan_name,
+        fee_amount,
+        payment_interval_seconds,
+        trial_period_seconds,
+        billing_mode,
+        missed_period_policy,
+        dunning,
+        refund_policy,
+        pause_policy,
+        metering,
+        revenue_splits,
+        unwrap_sol,
+        oracle_pricing,
+    } = config;
+
     require!(plan_id.len() <= 32, ErrorCodes::PlanIdTooLong);
     require!(plan_name.len() <= 64, ErrorCodes::PlanNameTooLong);
     require!(fee_amount > 0, ErrorCodes::InvalidFeeAmount);
     require!(payment_interval_seconds > 0, ErrorCodes::InvalidInterval);
+    require!(trial_period_seconds >= 0, ErrorCodes::InvalidTrialPeriod);
+    require!(missed_period_policy.is_valid(), ErrorCodes::InvalidMissedPeriodPolicy);
+    require!(dunning.is_valid(), ErrorCodes::InvalidDunningConfig);
+    require!(pause_policy.is_valid(), ErrorCodes::InvalidPausePolicy);
+    require!(metering.is_valid(), ErrorCodes::InvalidMeteringConfig);
+    require!(RevenueSplit::are_valid(&revenue_splits), ErrorCodes::InvalidRevenueSplits);
+    require!(!has_transfer_hook(&ctx.accounts.mint), ErrorCodes::UnsupportedMintExtension);
+    require!(
+        !unwrap_sol || is_native_mint(&ctx.accounts.mint.key()),
+        ErrorCodes::NativeMintRequired
+    );
+    // Split payees are paid in wSOL; only a single merchant payout is unwrapped
+    require!(
+        !unwrap_sol || revenue_splits.is_empty(),
+        ErrorCodes::UnwrapSolWithRevenueSplits
+    );
+
+    // Oracle-priced plans are converted when a charge is made, so nothing is
+    // charged up front at subscribe time
+    if let Some(pricing) = oracle_pricing.as_ref() {
+        require!(pricing.is_valid(&ctx.accounts.mint.key()), ErrorCodes::InvalidOraclePricing);
+        require!(
+            billing_mode == BillingMode::InArrears,
+            ErrorCodes::OraclePricingRequiresArrears
+        );
+    }
 
     let merchant_plan = &mut ctx.accounts.merchant_plan;
     
@@ -46,8 +89,22 @@ pub fn handler(
     merchant_plan.plan_name = plan_name;
     merchant_plan.fee_amount = fee_amount;
     merchant_plan.payment_interval = payment_interval_seconds;
+    merchant_plan.trial_period_seconds = trial_period_seconds;
+    merchant_plan.billing_mode = billing_mode;
+    merchant_plan.missed_period_policy = missed_period_policy;
+    merchant_plan.dunning = dunning;
+    merchant_plan.refund_policy = refund_policy;
+    merchant_plan.pause_policy = pause_policy;
+    merchant_plan.metering = metering;
+    merchant_plan.revenue_splits = revenue_splits;
+    merchant_plan.unwrap_sol = unwrap_sol;
+    merchant_plan.oracle_pricing = oracle_pricing;
     merchant_plan.is_active = true;
     merchant_plan.total_subscribers = 0;
+    merchant_plan.price_version = 0;
+    merchant_plan.pending_fee_amount = 0;
+    merchant_plan.price_effective_at = 0;
+    merchant_plan.price_cutoff_at = 0;
     merchant_plan.bump = ctx.bumps.merchant_plan;
 
     emit!(MerchantPlanRegistered {
@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+use crate::{SubscriptionState, UsageReported, ErrorCodes};
+
+#[derive(Accounts)]
+pub struct ReportUsage<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            subscription_state.user.as_ref(),
+            subscription_state.merchant_plan.as_ref(),
+            &subscription_state.subscription_index.to_le_bytes()
+        ],
+        bump = subscription_state.bump,
+        has_one = merchant @ ErrorCodes::UnauthorizedMerchant,
+        constraint = subscription_state.is_active @ ErrorCodes::SubscriptionInactive,
+        constraint = !subscription_state.is_paused(Clock::get()?.unix_timestamp) @ ErrorCodes::SubscriptionPaused,
+    )]
+    pub subscription_state: Account<'info, SubscriptionState>,
+
+    pub merchant: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<ReportUsage>, units: u64) -> Result<()> {
+    let subscription = &mut ctx.accounts.subscription_state;
+    let metering = subscription.metering;
+
+    require!(metering.is_metered(), ErrorCodes::PlanNotMetered);
+    require!(units > 0, ErrorCodes::InvalidUsageUnits);
+
+    let period_usage = subscription.period_usage
+        .checked_add(units)
+        .ok_or(ErrorCodes::MathOverflow)?;
+    require!(period_usage <= metering.max_units, ErrorCodes::UsageCapExceeded);
+
+    subscription.period_usage = period_usage;
+
+    emit!(UsageReported {
+        subscription_pda: subscription.key(),
+        merchant: subscription.merchant,
+        units,
+        period_usage,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+use crate::{MerchantPlan, MerchantPlanResumed, ErrorCodes};
+
+#[derive(Accounts)]
+pub struct ResumePlan<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"merchant_plan",
+            merchant_plan.merchant.as_ref(),
+            merchant_plan.mint.as_ref(),
+            merchant_plan.plan_id.as_bytes()
+        ],
+        bump = merchant_plan.bump,
+        has_one = merchant @ ErrorCodes::UnauthorizedMerchant
+    )]
+    pub merchant_plan: Account<'info, MerchantPlan>,
+
+    pub merchant: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<ResumePlan>) -> Result<()> {
+    let merchant_plan = &mut ctx.accounts.merchant_plan;
+
+    require!(!merchant_plan.is_active, ErrorCodes::PlanAlreadyActive);
+
+    merchant_plan.is_active = true;
+
+    emit!(MerchantPlanResumed {
+        plan_pda: merchant_plan.key(),
+        merchant: merchant_plan.merchant,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+use crate::{SubscriptionState, SubscriptionResumed, ErrorCodes};
+
+#[derive(Accounts)]
+pub struct ResumeSubscription<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            subscription_state.user.as_ref(),
+            subscription_state.merchant_plan.as_ref(),
+            &subscription_state.subscription_index.to_le_bytes()
+        ],
+        bump = subscription_state.bump,
+        constraint = subscription_state.has_pause() @ ErrorCodes::SubscriptionNotPaused,
+    )]
+    pub subscription_state: Account<'info, SubscriptionState>,
+
+    // The subscriber can resume early; anyone can resume once the pause ends
+    pub caller: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<ResumeSubscription>) -> Result<()> {
+    let subscription = &mut ctx.accounts.subscription_state;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    require!(
+        ctx.accounts.caller.key() == subscription.user
+            || current_time >= subscription.paused_until,
+        ErrorCodes::PauseNotOver
+    );
+
+    // Resuming late doesn't extend the pause past what was granted
+    let paused_seconds = subscription.end_pause(current_time)?;
+
+    emit!(SubscriptionResumed {
+        subscription_pda: subscription.key(),
+        user: subscription.user,
+        merchant: subscription.merchant,
+        paused_seconds,
+        next_payment_due: subscription.next_payment_due(),
+    });
+
+    Ok(())
+}
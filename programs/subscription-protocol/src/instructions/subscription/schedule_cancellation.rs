@@ -0,0 +1,48 @@
+use anchor_lang::prelude::*;
+use crate::{SubscriptionState, CancellationScheduled, ErrorCodes};
+
+#[derive(Accounts)]
+pub struct ScheduleCancellation<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            subscription_state.user.as_ref(),
+            subscription_state.merchant_plan.as_ref(),
+            &subscription_state.subscription_index.to_le_bytes()
+        ],
+        bump = subscription_state.bump,
+        has_one = user @ ErrorCodes::UnauthorizedCancellation,
+        constraint = subscription_state.is_active @ ErrorCodes::SubscriptionInactive,
+    )]
+    pub subscription_state: Account<'info, SubscriptionState>,
+
+    pub user: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<ScheduleCancellation>, cancel_at: Option<i64>) -> Result<()> {
+    let subscription = &mut ctx.accounts.subscription_state;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    // Without an explicit time the subscription runs out the period already paid for
+    let (scheduled_cancel_at, at_period_end) = match cancel_at {
+        Some(cancel_at) => {
+            require!(cancel_at >= current_time, ErrorCodes::InvalidCancellationTime);
+            (cancel_at, false)
+        }
+        None => (subscription.next_payment_due().max(current_time), true),
+    };
+
+    subscription.cancel_at_period_end = at_period_end;
+    subscription.scheduled_cancel_at = scheduled_cancel_at;
+
+    emit!(CancellationScheduled {
+        subscription_pda: subscription.key(),
+        user: subscription.user,
+        merchant: subscription.merchant,
+        cancel_at: scheduled_cancel_at,
+        at_period_end,
+    });
+
+    Ok(())
+}
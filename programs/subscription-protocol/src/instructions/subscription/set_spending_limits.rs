@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+use crate::{SubscriptionState, SpendingLimitsUpdated, ErrorCodes};
+
+#[derive(Accounts)]
+pub struct SetSpendingLimits<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            subscription_state.user.as_ref(),
+            subscription_state.merchant_plan.as_ref(),
+            &subscription_state.subscription_index.to_le_bytes()
+        ],
+        bump = subscription_state.bump,
+        has_one = user @ ErrorCodes::UnauthorizedSubscriber,
+        constraint = subscription_state.is_active @ ErrorCodes::SubscriptionInactive,
+    )]
+    pub subscription_state: Account<'info, SubscriptionState>,
+
+    pub user: Signer<'info>,
+}
+
+pub fn handler(
+    ctx: Context<SetSpendingLimits>,
+    max_amount_per_charge: Option<u64>,
+    max_total_spend: Option<u64>,
+    max_payments: Option<u32>,
+    max_price_slippage_bps: Option<u16>,
+) -> Result<()> {
+    let subscription = &mut ctx.accounts.subscription_state;
+
+    // Zero removes a limit. A limit below what's already been spent expires
+    // the subscription at its next charge.
+    if let Some(max_amount_per_charge) = max_amount_per_charge {
+        subscription.max_amount_per_charge = max_amount_per_charge;
+    }
+
+    if let Some(max_total_spend) = max_total_spend {
+        subscription.max_total_spend = max_total_spend;
+    }
+
+    if let Some(max_payments) = max_payments {
+        subscription.max_payments = max_payments;
+    }
+
+    if let Some(max_price_slippage_bps) = max_price_slippage_bps {
+        subscription.max_price_slippage_bps = max_price_slippage_bps;
+    }
+
+    emit!(SpendingLimitsUpdated {
+        subscription_pda: subscription.key(),
+        user: subscription.user,
+        max_amount_per_charge: subscription.max_amount_per_charge,
+        max_total_spend: subscription.max_total_spend,
+        max_payments: subscription.max_payments,
+        max_price_slippage_bps: subscription.max_price_slippage_bps,
+    });
+
+    Ok(())
+}
@@ -1,12 +1,18 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::TokenAccount;
+use anchor_spl::token_interface::{Mint, TokenInterface, TokenAccount};
 use crate::{
-    SubscriptionState, SubscriptionWallet, MerchantPlan,
-    SessionTokenTracker, SubscriptionCreated, ErrorCodes
+    SubscriptionState, SubscriptionWallet, MerchantPlan, BillingMode, ProtocolConfig,
+    YieldVault, SessionTokenTracker, TrialUsage, Coupon, SubscriptionCreated, TrialStarted,
+    CouponApplied, PaymentExecuted, ErrorCodes
+};
+use crate::utils::{
+    calculate_subscription_commitment, calculate_payment_split, price_periods, transfer_payment,
+    PaymentAccounts, quote_for_plan, settlement_amount, merchant_payout_account, unwrap_sol_payout,
+    wallet_yield_value
 };
 
 #[derive(Accounts)]
-#[instruction(session_token: String)]
+#[instruction(session_token: String, subscription_index: u16)]
 pub struct SubscribeWithWallet<'info> {
     #[account(
         init,
@@ -15,8 +21,8 @@ pub struct SubscribeWithWallet<'info> {
         seeds = [
             b"subscription",
             user.key().as_ref(),
-            merchant_plan.merchant.as_ref(),
-            merchant_plan.mint.as_ref()
+            merchant_plan.key().as_ref(),
+            &subscription_index.to_le_bytes()
         ],
         bump
     )]
@@ -35,12 +41,27 @@ pub struct SubscribeWithWallet<'info> {
     )]
     pub session_token_tracker: Account<'info, SessionTokenTracker>,
 
+    // Required when the plan offers a trial; records one trial per user per plan
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + TrialUsage::INIT_SPACE,
+        seeds = [
+            b"trial_usage",
+            user.key().as_ref(),
+            merchant_plan.key().as_ref()
+        ],
+        bump
+    )]
+    pub trial_usage: Option<Account<'info, TrialUsage>>,
+
     #[account(
         mut,
         seeds = [
             b"subscription_wallet",
             subscription_wallet.owner.as_ref(),
-            subscription_wallet.mint.as_ref()
+            subscription_wallet.mint.as_ref(),
+            &SubscriptionWallet::index_seed(subscription_wallet.wallet_index)
         ],
         bump = subscription_wallet.bump,
     )]
@@ -56,17 +77,100 @@ pub struct SubscribeWithWallet<'info> {
     pub user: Signer<'info>,
 
     #[account(
+        mut,
         token::mint = subscription_wallet.mint,
         token::authority = subscription_wallet
     )]
-    pub wallet_token_account: Account<'info, TokenAccount>,
+    pub wallet_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    // Payment accounts (required for pay-in-advance plans)
+    #[account(
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump,
+    )]
+    pub protocol_config: Option<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        mut,
+        token::mint = subscription_wallet.mint,
+        constraint = merchant_token_account.owner == merchant_plan.merchant @ ErrorCodes::InvalidMerchantAccount
+    )]
+    pub merchant_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = subscription_wallet.mint,
+    )]
+    pub protocol_treasury: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = subscription_wallet.mint)]
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    /// CHECK: Pyth price update, validated against the plan's oracle settings
+    /// (oracle-priced plans need it and `mint` to convert the buffer into tokens)
+    pub price_feed: Option<UncheckedAccount<'info>>,
+
+    // Optional yield vault accounts (required when the wallet holds yield shares,
+    // which count towards the buffer)
+    #[account(
+        seeds = [b"yield_vault", subscription_wallet.mint.as_ref()],
+        bump = yield_vault.bump,
+    )]
+    pub yield_vault: Option<Account<'info, YieldVault>>,
+
+    pub vault_buffer: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub jupiter_ftoken_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Jupiter Lend lending account
+    pub jupiter_lending: Option<AccountInfo<'info>>,
+
+    // Optional promotion code redeemed at signup
+    #[account(
+        mut,
+        seeds = [
+            b"coupon",
+            coupon.merchant.as_ref(),
+            coupon.mint.as_ref(),
+            coupon.code.as_bytes()
+        ],
+        bump = coupon.bump,
+        constraint = coupon.merchant == merchant_plan.merchant @ ErrorCodes::CouponMismatch,
+        constraint = coupon.mint == merchant_plan.mint @ ErrorCodes::CouponMismatch,
+    )]
+    pub coupon: Option<Account<'info, Coupon>>,
+
+    // Native SOL payout (plans with unwrap_sol): the merchant's share passes through
+    // this temporary wSOL account and is paid to `merchant` as SOL
+    #[account(
+        init,
+        payer = user,
+        seeds = [b"sol_unwrap", merchant_plan.merchant.as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = sol_unwrap_account,
+        token::token_program = token_program,
+    )]
+    pub sol_unwrap_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = merchant_plan.merchant @ ErrorCodes::InvalidMerchantAccount
+    )]
+    pub merchant: Option<SystemAccount<'info>>,
 
     pub system_program: Program<'info, System>,
 }
 
-pub fn handler(
-    ctx: Context<SubscribeWithWallet>,
+// remaining_accounts: one payee token account per plan revenue split,
+// needed only when the first period is charged at signup
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SubscribeWithWallet<'info>>,
     session_token: String,
+    subscription_index: u16,
+    quantity: u32,
 ) -> Result<()> {
     let merchant_plan = &ctx.accounts.merchant_plan;
     let wallet = &mut ctx.accounts.subscription_wallet;
@@ -76,8 +180,11 @@ pub fn handler(
         wallet.owner == ctx.accounts.user.key(),
         ErrorCodes::UnauthorizedWalletAccess
     );
+    // Oracle-priced plans settle in any mint they have a feed for
+    require!(merchant_plan.settles_in(&wallet.mint), ErrorCodes::SettlementMintNotSupported);
     require!(session_token.len() <= 64, ErrorCodes::SessionTokenTooLong);
     require!(!session_token.is_empty(), ErrorCodes::SessionTokenRequired);
+    require!(quantity > 0, ErrorCodes::InvalidQuantity);
 
     let tracker = &ctx.accounts.session_token_tracker;
     if tracker.is_used {
@@ -89,12 +196,41 @@ pub fn handler(
         return Err(ErrorCodes::SessionTokenAlreadyUsed.into());
     }
 
-    // Calculate required buffer (3 months)
-    let min_buffer = merchant_plan.fee_amount.checked_mul(3).unwrap();
-    let wallet_balance = ctx.accounts.wallet_token_account.amount;
+    let current_time = Clock::get()?.unix_timestamp;
+    let fee_amount = merchant_plan.current_fee(current_time);
+    let period_fee = fee_amount
+        .checked_mul(quantity as u64)
+        .ok_or(ErrorCodes::MathOverflow)?;
+
+    let quote = quote_for_plan(
+        merchant_plan,
+        ctx.accounts.price_feed.as_ref().map(|account| account.as_ref()),
+        ctx.accounts.mint.as_ref(),
+        current_time,
+    )?;
+
+    // Calculate required buffer (3 months, all seats), in tokens at today's price
+    let min_buffer = settlement_amount(
+        quote.as_ref(),
+        calculate_subscription_commitment(period_fee)?,
+    )?;
+    // The buffer comes on top of what the wallet already commits to other
+    // subscriptions; yield shares count towards it as they do on withdraw
+    let wallet_balance = ctx.accounts.wallet_token_account.amount
+        .checked_add(wallet_yield_value(
+            wallet,
+            ctx.accounts.yield_vault.as_ref(),
+            ctx.accounts.vault_buffer.as_ref(),
+            ctx.accounts.jupiter_ftoken_account.as_ref(),
+            ctx.accounts.jupiter_lending.as_ref(),
+        )?)
+        .ok_or(ErrorCodes::MathOverflow)?;
+    let required_balance = wallet.committed_amount
+        .checked_add(min_buffer)
+        .ok_or(ErrorCodes::MathOverflow)?;
 
     require!(
-        wallet_balance >= min_buffer,
+        wallet_balance >= required_balance,
         ErrorCodes::InsufficientWalletBalance
     );
 
@@ -104,23 +240,100 @@ pub fn handler(
     subscription.user = ctx.accounts.user.key();
     subscription.subscription_wallet = wallet.key();
     subscription.merchant = merchant_plan.merchant;
-    subscription.mint = merchant_plan.mint;
+    subscription.mint = wallet.mint;
     subscription.merchant_plan = ctx.accounts.merchant_plan.key();
-    subscription.fee_amount = merchant_plan.fee_amount;
+    subscription.fee_amount = fee_amount;
     subscription.payment_interval = merchant_plan.payment_interval;
-    subscription.last_payment_timestamp = Clock::get()?.unix_timestamp;
+    subscription.last_payment_timestamp = current_time;
+    subscription.trial_ends_at = 0;
     subscription.session_token = session_token.clone();
     subscription.bump = ctx.bumps.subscription_state;
+    subscription.subscription_index = subscription_index;
+    subscription.proration_credit = 0;
+    subscription.total_refunded = 0;
+    subscription.paused_at = 0;
+    subscription.paused_until = 0;
+    subscription.max_amount_per_charge = 0;
+    subscription.max_total_spend = 0;
+    subscription.max_payments = 0;
+    subscription.expired_at = 0;
+    subscription.period_usage = 0;
+    subscription.quantity = quantity;
+    subscription.metering = merchant_plan.metering;
+    subscription.coupon = None;
+    subscription.coupon_discount = None;
+    subscription.coupon_periods_remaining = 0;
+    subscription.max_price_slippage_bps = 0;
+
+    if let Some(coupon) = ctx.accounts.coupon.as_mut() {
+        require!(coupon.is_redeemable(current_time), ErrorCodes::CouponNotRedeemable);
+
+        coupon.redemptions = coupon.redemptions
+            .checked_add(1)
+            .ok_or(ErrorCodes::MathOverflow)?;
+        subscription.apply_coupon(coupon.key(), coupon.discount, coupon.duration_periods);
+
+        emit!(CouponApplied {
+            subscription_pda: subscription.key(),
+            coupon_pda: coupon.key(),
+            user: subscription.user,
+            merchant: subscription.merchant,
+            duration_periods: coupon.duration_periods,
+        });
+    }
     subscription.is_active = true;
     subscription.total_paid = 0;
     subscription.payment_count = 0;
+    subscription.last_charge_amount = 0;
+    subscription.last_charge_refunded = 0;
+    subscription.committed_amount = min_buffer;
+
+    // Subscribing after a price change is announced counts as accepting it
+    subscription.price_version = if current_time < merchant_plan.price_effective_at {
+        merchant_plan.price_version.saturating_sub(1)
+    } else {
+        merchant_plan.price_version
+    };
+    subscription.accepted_price_version = merchant_plan.price_version;
+    subscription.accepted_fee_amount = merchant_plan.pending_fee_amount;
+    subscription.accepted_effective_at = merchant_plan.price_effective_at;
+    subscription.price_action_required = false;
+    subscription.cancel_at_period_end = false;
+    subscription.scheduled_cancel_at = 0;
+
+    // Start the free trial if the user hasn't had one on this plan yet
+    if merchant_plan.has_trial() {
+        let trial_usage = ctx.accounts.trial_usage
+            .as_mut()
+            .ok_or(ErrorCodes::TrialUsageRequired)?;
+
+        if !trial_usage.is_used() {
+            subscription.trial_ends_at = current_time
+                .checked_add(merchant_plan.trial_period_seconds)
+                .ok_or(ErrorCodes::MathOverflow)?;
+
+            trial_usage.user = ctx.accounts.user.key();
+            trial_usage.merchant_plan = ctx.accounts.merchant_plan.key();
+            trial_usage.subscription = subscription.key();
+            trial_usage.started_at = current_time;
+            trial_usage.bump = ctx.bumps.trial_usage.unwrap();
+
+            emit!(TrialStarted {
+                subscription_pda: subscription.key(),
+                user: subscription.user,
+                merchant: subscription.merchant,
+                plan_pda: ctx.accounts.merchant_plan.key(),
+                trial_ends_at: subscription.trial_ends_at,
+            });
+        }
+    }
 
     // Mark session token as used
     let tracker = &mut ctx.accounts.session_token_tracker;
     tracker.session_token = session_token.clone();
     tracker.user = ctx.accounts.user.key();
     tracker.subscription = subscription.key();
-    tracker.timestamp = Clock::get()?.unix_timestamp;
+    tracker.timestamp = current_time;
     tracker.is_used = true;
     tracker.bump = ctx.bumps.session_token_tracker;
 
@@ -128,6 +341,7 @@ pub fn handler(
     wallet.total_subscriptions = wallet.total_subscriptions
         .checked_add(1)
         .ok_or(ErrorCodes::MathOverflow)?;
+    wallet.commit(min_buffer)?;
 
     let merchant_plan = &mut ctx.accounts.merchant_plan;
     merchant_plan.total_subscribers = merchant_plan.total_subscribers
@@ -140,8 +354,100 @@ pub fn handler(
         wallet: subscription.subscription_wallet,
         merchant: subscription.merchant,
         plan_id: merchant_plan.plan_id.clone(),
-        session_token: session_token,
+        subscription_index,
+        quantity,
+        session_token,
     });
 
+    // Pay-in-advance plans charge the first period now, unless a trial defers it
+    if merchant_plan.billing_mode == BillingMode::InAdvance && !subscription.is_in_trial() {
+        let protocol_config = ctx.accounts.protocol_config
+            .as_ref()
+            .ok_or(ErrorCodes::PaymentAccountsRequired)?;
+        let merchant_token_account = ctx.accounts.merchant_token_account
+            .as_ref()
+            .ok_or(ErrorCodes::PaymentAccountsRequired)?;
+        let protocol_treasury = ctx.accounts.protocol_treasury
+            .as_ref()
+            .ok_or(ErrorCodes::PaymentAccountsRequired)?;
+        let mint = ctx.accounts.mint
+            .as_ref()
+            .ok_or(ErrorCodes::PaymentAccountsRequired)?;
+        let token_program = ctx.accounts.token_program
+            .as_ref()
+            .ok_or(ErrorCodes::PaymentAccountsRequired)?;
+
+        require!(
+            protocol_treasury.owner == protocol_config.treasury,
+            ErrorCodes::InvalidTreasuryAccount
+        );
+
+        let price = price_periods(subscription, 1)?;
+        let amount = price.gross_amount;
+        let coupon = subscription.coupon;
+        subscription.consume_coupon(price.discounted_periods);
+
+        let (protocol_fee, merchant_receives) = calculate_payment_split(amount, protocol_config)?;
+
+        let payee_payouts = transfer_payment(
+            wallet,
+            &PaymentAccounts {
+                wallet_token_account: &ctx.accounts.wallet_token_account,
+                merchant_token_account: merchant_payout_account(
+                    merchant_plan,
+                    merchant_token_account,
+                    ctx.accounts.sol_unwrap_account.as_ref(),
+                )?,
+                protocol_treasury,
+                payee_token_accounts: ctx.remaining_accounts,
+                mint,
+                token_program,
+            },
+            merchant_plan,
+            merchant_receives,
+            protocol_fee,
+        )?;
+
+        subscription.total_paid = amount;
+        subscription.payment_count = 1;
+        subscription.last_charge_amount = amount;
+        wallet.total_spent = wallet.total_spent
+            .checked_add(amount)
+            .ok_or(ErrorCodes::MathOverflow)?;
+
+        emit!(PaymentExecuted {
+            subscription_pda: subscription.key(),
+            wallet_pda: wallet.key(),
+            user: subscription.user,
+            merchant: subscription.merchant,
+            amount,
+            protocol_fee,
+            merchant_received: merchant_receives,
+            payment_number: subscription.payment_count,
+            periods_settled: 1,
+            keeper: None,
+            keeper_reward: 0,
+            usage_units: 0,
+            included_units: 0,
+            overage_amount: 0,
+            discount_amount: price.discount_amount,
+            coupon: if price.discounted_periods > 0 { coupon } else { None },
+            payee_payouts,
+            oracle_conversion: None,
+        });
+    }
+
+    // The temporary wSOL account is closed even when nothing was charged
+    if let Some(token_program) = ctx.accounts.token_program.as_ref() {
+        unwrap_sol_payout(
+            ctx.accounts.sol_unwrap_account.as_ref(),
+            ctx.bumps.sol_unwrap_account,
+            ctx.accounts.merchant.as_ref(),
+            Some(&ctx.accounts.user),
+            Some(&ctx.accounts.system_program),
+            token_program,
+        )?;
+    }
+
     Ok(())
 }
\ No newline at end of file
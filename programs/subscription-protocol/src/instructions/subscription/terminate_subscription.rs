@@ -0,0 +1,175 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenInterface, TokenAccount, TransferChecked};
+use crate::{
+    SubscriptionState, SubscriptionWallet, MerchantPlan, ProtocolConfig,
+    TerminationReason, SubscriptionTerminated, RefundIssued, ErrorCodes
+};
+use crate::utils::{unused_period_amount, calculate_refund, reverse_protocol_fee};
+
+#[derive(Accounts)]
+pub struct TerminateSubscription<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            subscription_state.user.as_ref(),
+            subscription_state.merchant_plan.as_ref(),
+            &subscription_state.subscription_index.to_le_bytes()
+        ],
+        bump = subscription_state.bump,
+        has_one = merchant @ ErrorCodes::UnauthorizedMerchant,
+        has_one = user,
+        close = user
+    )]
+    pub subscription_state: Account<'info, SubscriptionState>,
+
+    #[account(
+        mut,
+        constraint = subscription_wallet.key() == subscription_state.subscription_wallet
+    )]
+    pub subscription_wallet: Account<'info, SubscriptionWallet>,
+
+    #[account(
+        mut,
+        constraint = merchant_plan.key() == subscription_state.merchant_plan @ ErrorCodes::InvalidMerchantPlan,
+    )]
+    pub merchant_plan: Account<'info, MerchantPlan>,
+
+    pub merchant: Signer<'info>,
+
+    /// CHECK: Subscriber receiving the rent; verified by has_one
+    #[account(mut)]
+    pub user: AccountInfo<'info>,
+
+    // Refund accounts (required when refunding the unused period)
+    #[account(
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump,
+    )]
+    pub protocol_config: Option<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        mut,
+        token::mint = subscription_state.mint,
+        token::authority = merchant
+    )]
+    pub merchant_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = subscription_state.mint,
+        token::authority = subscription_wallet
+    )]
+    pub wallet_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = subscription_state.mint)]
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    // Protocol fee reversal (required when the plan's refund policy reverses the fee)
+    #[account(
+        mut,
+        token::mint = subscription_state.mint,
+    )]
+    pub protocol_treasury: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub protocol_authority: Option<Signer<'info>>,
+}
+
+pub fn handler(
+    ctx: Context<TerminateSubscription>,
+    reason: TerminationReason,
+    refund_unused: bool,
+) -> Result<()> {
+    let subscription = &ctx.accounts.subscription_state;
+    let merchant_plan = &ctx.accounts.merchant_plan;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    let amount = if refund_unused {
+        unused_period_amount(subscription, merchant_plan, current_time)?
+    } else {
+        0
+    };
+
+    let mut refund_amount = 0;
+    if amount > 0 {
+        let protocol_config = ctx.accounts.protocol_config
+            .as_ref()
+            .ok_or(ErrorCodes::RefundAccountsRequired)?;
+        let merchant_token_account = ctx.accounts.merchant_token_account
+            .as_ref()
+            .ok_or(ErrorCodes::RefundAccountsRequired)?;
+        let wallet_token_account = ctx.accounts.wallet_token_account
+            .as_ref()
+            .ok_or(ErrorCodes::RefundAccountsRequired)?;
+        let mint = ctx.accounts.mint
+            .as_ref()
+            .ok_or(ErrorCodes::RefundAccountsRequired)?;
+        let token_program = ctx.accounts.token_program
+            .as_ref()
+            .ok_or(ErrorCodes::RefundAccountsRequired)?;
+
+        let (refund, protocol_fee_reversed) = calculate_refund(
+            amount,
+            protocol_config,
+            &merchant_plan.refund_policy,
+        )?;
+
+        // protocol_config is optional here, so the reversal accounts are checked by hand
+        if let Some(protocol_treasury) = ctx.accounts.protocol_treasury.as_ref() {
+            require_keys_eq!(protocol_treasury.owner, protocol_config.treasury, ErrorCodes::InvalidTreasuryAccount);
+        }
+        if let Some(protocol_authority) = ctx.accounts.protocol_authority.as_ref() {
+            require_keys_eq!(protocol_authority.key(), protocol_config.authority, ErrorCodes::UnauthorizedProtocolUpdate);
+        }
+
+        let cpi_accounts = TransferChecked {
+            from: merchant_token_account.to_account_info(),
+            mint: mint.to_account_info(),
+            to: wallet_token_account.to_account_info(),
+            authority: ctx.accounts.merchant.to_account_info(),
+        };
+        token_interface::transfer_checked(
+            CpiContext::new(token_program.to_account_info(), cpi_accounts),
+            refund,
+            mint.decimals,
+        )?;
+        reverse_protocol_fee(
+            ctx.accounts.protocol_treasury.as_ref(),
+            ctx.accounts.protocol_authority.as_ref(),
+            wallet_token_account,
+            mint,
+            token_program,
+            protocol_fee_reversed,
+        )?;
+        refund_amount = refund + protocol_fee_reversed;
+
+        emit!(RefundIssued {
+            subscription_pda: subscription.key(),
+            user: subscription.user,
+            merchant: subscription.merchant,
+            payment_number: subscription.payment_count,
+            amount: refund_amount,
+            protocol_fee_reversed,
+            prorated: true,
+        });
+    }
+
+    let wallet = &mut ctx.accounts.subscription_wallet;
+    wallet.total_subscriptions = wallet.total_subscriptions.saturating_sub(1);
+    wallet.release(subscription.committed_amount);
+
+    let merchant_plan = &mut ctx.accounts.merchant_plan;
+    merchant_plan.total_subscribers = merchant_plan.total_subscribers.saturating_sub(1);
+
+    emit!(SubscriptionTerminated {
+        subscription_pda: subscription.key(),
+        user: subscription.user,
+        merchant: subscription.merchant,
+        reason,
+        refund_amount,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+use crate::{SubscriptionState, CancellationUndone, ErrorCodes};
+
+#[derive(Accounts)]
+pub struct UndoCancellation<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            subscription_state.user.as_ref(),
+            subscription_state.merchant_plan.as_ref(),
+            &subscription_state.subscription_index.to_le_bytes()
+        ],
+        bump = subscription_state.bump,
+        has_one = user @ ErrorCodes::UnauthorizedCancellation,
+        constraint = subscription_state.is_active @ ErrorCodes::SubscriptionInactive,
+    )]
+    pub subscription_state: Account<'info, SubscriptionState>,
+
+    pub user: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<UndoCancellation>) -> Result<()> {
+    let subscription = &mut ctx.accounts.subscription_state;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    require!(
+        subscription.has_scheduled_cancellation(),
+        ErrorCodes::NoCancellationScheduled
+    );
+    require!(
+        current_time < subscription.scheduled_cancel_at,
+        ErrorCodes::CancellationAlreadyEffective
+    );
+
+    subscription.cancel_at_period_end = false;
+    subscription.scheduled_cancel_at = 0;
+
+    emit!(CancellationUndone {
+        subscription_pda: subscription.key(),
+        user: subscription.user,
+        merchant: subscription.merchant,
+    });
+
+    Ok(())
+}
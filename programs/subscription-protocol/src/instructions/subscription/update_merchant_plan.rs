@@ -0,0 +1,138 @@
+use anchor_lang::prelude::*;
+use crate::{
+    MerchantPlan, PlanUpdate, RevenueSplit, MerchantPlanUpdated, ErrorCodes
+};
+use crate::utils::is_native_mint;
+
+#[derive(Accounts)]
+pub struct UpdateMerchantPlan<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"merchant_plan",
+            merchant_plan.merchant.as_ref(),
+            merchant_plan.mint.as_ref(),
+            merchant_plan.plan_id.as_bytes()
+        ],
+        bump = merchant_plan.bump,
+        has_one = merchant @ ErrorCodes::UnauthorizedMerchant
+    )]
+    pub merchant_plan: Account<'info, MerchantPlan>,
+
+    pub merchant: Signer<'info>,
+}
+
+pub fn handler(
+    ctx: Context<UpdateMerchantPlan>,
+    update: PlanUpdate,
+) -> Result<()> {
+    let PlanUpdate {
+        plan_name,
+        fee_amount,
+        payment_interval_seconds,
+        trial_period_seconds,
+        missed_period_policy,
+        dunning,
+        refund_policy,
+        pause_policy,
+        metering,
+        revenue_splits,
+        unwrap_sol,
+        oracle_pricing,
+    } = update;
+    let merchant_plan = &mut ctx.accounts.merchant_plan;
+
+    if let Some(plan_name) = plan_name {
+        require!(plan_name.len() <= 64, ErrorCodes::PlanNameTooLong);
+        merchant_plan.plan_name = plan_name;
+    }
+
+    // Existing subscriptions keep the fee and interval they subscribed with.
+    // Use publish_price_change to move existing subscribers to a new price.
+    if let Some(fee_amount) = fee_amount {
+        require!(fee_amount > 0, ErrorCodes::InvalidFeeAmount);
+        require!(merchant_plan.price_version == 0, ErrorCodes::PriceVersioned);
+        merchant_plan.fee_amount = fee_amount;
+    }
+
+    if let Some(payment_interval_seconds) = payment_interval_seconds {
+        require!(payment_interval_seconds > 0, ErrorCodes::InvalidInterval);
+        merchant_plan.payment_interval = payment_interval_seconds;
+    }
+
+    if let Some(trial_period_seconds) = trial_period_seconds {
+        require!(trial_period_seconds >= 0, ErrorCodes::InvalidTrialPeriod);
+        merchant_plan.trial_period_seconds = trial_period_seconds;
+    }
+
+    if let Some(missed_period_policy) = missed_period_policy {
+        require!(missed_period_policy.is_valid(), ErrorCodes::InvalidMissedPeriodPolicy);
+        merchant_plan.missed_period_policy = missed_period_policy;
+    }
+
+    if let Some(dunning) = dunning {
+        require!(dunning.is_valid(), ErrorCodes::InvalidDunningConfig);
+        merchant_plan.dunning = dunning;
+    }
+
+    if let Some(refund_policy) = refund_policy {
+        merchant_plan.refund_policy = refund_policy;
+    }
+
+    // Subscriptions already paused keep the pause they started with
+    if let Some(pause_policy) = pause_policy {
+        require!(pause_policy.is_valid(), ErrorCodes::InvalidPausePolicy);
+        merchant_plan.pause_policy = pause_policy;
+    }
+
+    // Like the fee, metering rates only apply to new subscriptions and plan changes
+    if let Some(metering) = metering {
+        require!(metering.is_valid(), ErrorCodes::InvalidMeteringConfig);
+        merchant_plan.metering = metering;
+    }
+
+    // An empty list sends everything back to the merchant token account
+    if let Some(revenue_splits) = revenue_splits {
+        require!(RevenueSplit::are_valid(&revenue_splits), ErrorCodes::InvalidRevenueSplits);
+        merchant_plan.revenue_splits = revenue_splits;
+    }
+
+    if let Some(unwrap_sol) = unwrap_sol {
+        require!(
+            !unwrap_sol || is_native_mint(&merchant_plan.mint),
+            ErrorCodes::NativeMintRequired
+        );
+        merchant_plan.unwrap_sol = unwrap_sol;
+    }
+
+    // Split payees are paid in wSOL; only a single merchant payout is unwrapped
+    require!(
+        !merchant_plan.unwrap_sol || !merchant_plan.has_revenue_splits(),
+        ErrorCodes::UnwrapSolWithRevenueSplits
+    );
+
+    // Feeds and their checks can be retuned and mints added, but a plan can't
+    // switch between reference-currency and token pricing, and can't drop a
+    // mint existing subscribers may be paying in
+    if let Some(pricing) = oracle_pricing {
+        let current = merchant_plan.oracle_pricing
+            .as_ref()
+            .ok_or(ErrorCodes::PricingCurrencyMismatch)?;
+        require!(
+            pricing.is_valid(&merchant_plan.mint)
+                && current.price_feeds.iter().all(|feed| pricing.feed_for(&feed.mint).is_some()),
+            ErrorCodes::InvalidOraclePricing
+        );
+        merchant_plan.oracle_pricing = Some(pricing);
+    }
+
+    emit!(MerchantPlanUpdated {
+        plan_pda: merchant_plan.key(),
+        plan_name: merchant_plan.plan_name.clone(),
+        fee_amount: merchant_plan.current_fee(Clock::get()?.unix_timestamp),
+        payment_interval: merchant_plan.payment_interval,
+        trial_period_seconds: merchant_plan.trial_period_seconds,
+    });
+
+    Ok(())
+}
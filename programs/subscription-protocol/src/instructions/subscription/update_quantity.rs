@@ -0,0 +1,236 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenInterface, TokenAccount};
+use crate::{
+    SubscriptionState, SubscriptionWallet, MerchantPlan, ProtocolConfig,
+    QuantityUpdated, ErrorCodes
+};
+use crate::utils::{
+    calculate_subscription_commitment, calculate_period_proration, split_charge, transfer_payment,
+    PaymentAccounts, payment_cost, quote_for_plan, settlement_amount, resume_lapsed_pause,
+    merchant_payout_account, unwrap_sol_payout
+};
+
+#[derive(Accounts)]
+pub struct UpdateQuantity<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            subscription_state.user.as_ref(),
+            subscription_state.merchant_plan.as_ref(),
+            &subscription_state.subscription_index.to_le_bytes()
+        ],
+        bump = subscription_state.bump,
+        has_one = user @ ErrorCodes::UnauthorizedSubscriber,
+        constraint = subscription_state.is_active @ ErrorCodes::SubscriptionInactive,
+        constraint = !subscription_state.is_suspended @ ErrorCodes::SubscriptionSuspended,
+        constraint = !subscription_state.is_paused(Clock::get()?.unix_timestamp) @ ErrorCodes::SubscriptionPaused,
+    )]
+    pub subscription_state: Account<'info, SubscriptionState>,
+
+    #[account(
+        mut,
+        constraint = subscription_wallet.key() == subscription_state.subscription_wallet
+    )]
+    pub subscription_wallet: Account<'info, SubscriptionWallet>,
+
+    #[account(
+        constraint = merchant_plan.key() == subscription_state.merchant_plan @ ErrorCodes::InvalidMerchantPlan,
+    )]
+    pub merchant_plan: Account<'info, MerchantPlan>,
+
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        mut,
+        token::mint = subscription_wallet.mint,
+        token::authority = subscription_wallet
+    )]
+    pub wallet_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = subscription_state.mint,
+        constraint = merchant_token_account.owner == merchant_plan.merchant @ ErrorCodes::InvalidMerchantAccount
+    )]
+    pub merchant_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = subscription_state.mint,
+        constraint = protocol_treasury.owner == protocol_config.treasury @ ErrorCodes::InvalidTreasuryAccount
+    )]
+    pub protocol_treasury: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = subscription_state.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Pyth price update, validated against the plan's oracle settings
+    /// (required for oracle-priced plans)
+    pub price_feed: Option<UncheckedAccount<'info>>,
+
+    // Native SOL payout (plans with unwrap_sol): the merchant's share passes through
+    // this temporary wSOL account and is paid to `merchant` as SOL
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"sol_unwrap", merchant_plan.merchant.as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = sol_unwrap_account,
+        token::token_program = token_program,
+    )]
+    pub sol_unwrap_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = merchant_plan.merchant @ ErrorCodes::InvalidMerchantAccount
+    )]
+    pub merchant: Option<SystemAccount<'info>>,
+
+    // Funds the temporary account's rent, which is returned in the same instruction
+    #[account(mut)]
+    pub payer: Option<Signer<'info>>,
+
+    pub system_program: Option<Program<'info, System>>,
+}
+
+// remaining_accounts: one payee token account per plan revenue split,
+// needed only when the increase is charged
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, UpdateQuantity<'info>>,
+    new_quantity: u32,
+) -> Result<()> {
+    let subscription = &mut ctx.accounts.subscription_state;
+    let wallet = &mut ctx.accounts.subscription_wallet;
+    let current_time = Clock::get()?.unix_timestamp;
+    resume_lapsed_pause(subscription, current_time)?;
+
+    require!(new_quantity > 0, ErrorCodes::InvalidQuantity);
+    require!(
+        !subscription.is_past_due && !subscription.is_payment_due(current_time),
+        ErrorCodes::PaymentOutstanding
+    );
+
+    let old_quantity = subscription.quantity;
+    let old_period_fee = subscription.period_fee().ok_or(ErrorCodes::MathOverflow)?;
+    let new_period_fee = subscription.fee_amount
+        .checked_mul(new_quantity as u64)
+        .ok_or(ErrorCodes::MathOverflow)?;
+
+    let (prorated_charge, prorated_credit) = calculate_period_proration(
+        subscription,
+        ctx.accounts.merchant_plan.billing_mode,
+        old_period_fee,
+        new_period_fee,
+        subscription.payment_interval,
+        current_time,
+    )?;
+
+    // Oracle-priced plans settle the proration in tokens at today's price
+    let quote = quote_for_plan(
+        &ctx.accounts.merchant_plan,
+        ctx.accounts.price_feed.as_ref().map(|account| account.as_ref()),
+        Some(&ctx.accounts.mint),
+        current_time,
+    )?;
+    let prorated_charge = settlement_amount(quote.as_ref(), prorated_charge)?;
+    let prorated_credit = settlement_amount(quote.as_ref(), prorated_credit)?;
+
+    // The withdrawal lock scales with the number of seats
+    let new_commitment = settlement_amount(
+        quote.as_ref(),
+        calculate_subscription_commitment(new_period_fee)?,
+    )?;
+
+    // Adding seats needs the larger buffer on top of whatever is charged now
+    let split = split_charge(prorated_charge, &ctx.accounts.protocol_config, None)?;
+    let charge_cost = if prorated_charge > 0 {
+        payment_cost(
+            &ctx.accounts.merchant_plan,
+            &ctx.accounts.mint,
+            split.merchant_receives,
+            &[split.treasury_receives],
+        )?
+    } else {
+        0
+    };
+    if new_quantity > old_quantity {
+        require!(
+            ctx.accounts.wallet_token_account.amount
+                >= new_commitment.checked_add(charge_cost).ok_or(ErrorCodes::MathOverflow)?,
+            ErrorCodes::InsufficientWalletBalance
+        );
+    }
+
+    if prorated_charge > 0 {
+        transfer_payment(
+            wallet,
+            &PaymentAccounts {
+                wallet_token_account: &ctx.accounts.wallet_token_account,
+                merchant_token_account: merchant_payout_account(
+                    &ctx.accounts.merchant_plan,
+                    &ctx.accounts.merchant_token_account,
+                    ctx.accounts.sol_unwrap_account.as_ref(),
+                )?,
+                protocol_treasury: &ctx.accounts.protocol_treasury,
+                payee_token_accounts: ctx.remaining_accounts,
+                mint: &ctx.accounts.mint,
+                token_program: &ctx.accounts.token_program,
+            },
+            &ctx.accounts.merchant_plan,
+            split.merchant_receives,
+            split.treasury_receives,
+        )?;
+
+        subscription.total_paid = subscription.total_paid
+            .checked_add(prorated_charge)
+            .ok_or(ErrorCodes::MathOverflow)?;
+        // The prorated charge pays for the current period, so it is refundable
+        // along with that period's payment
+        subscription.last_charge_amount = subscription.last_charge_amount
+            .checked_add(prorated_charge)
+            .ok_or(ErrorCodes::MathOverflow)?;
+        wallet.total_spent = wallet.total_spent
+            .checked_add(prorated_charge)
+            .ok_or(ErrorCodes::MathOverflow)?;
+    }
+
+    unwrap_sol_payout(
+        ctx.accounts.sol_unwrap_account.as_ref(),
+        ctx.bumps.sol_unwrap_account,
+        ctx.accounts.merchant.as_ref(),
+        ctx.accounts.payer.as_ref(),
+        ctx.accounts.system_program.as_ref(),
+        &ctx.accounts.token_program,
+    )?;
+
+    subscription.proration_credit = subscription.proration_credit
+        .checked_add(prorated_credit)
+        .ok_or(ErrorCodes::MathOverflow)?;
+
+    wallet.recommit(subscription.committed_amount, new_commitment)?;
+
+    subscription.quantity = new_quantity;
+    subscription.committed_amount = new_commitment;
+
+    emit!(QuantityUpdated {
+        subscription_pda: subscription.key(),
+        user: subscription.user,
+        merchant: subscription.merchant,
+        old_quantity,
+        new_quantity,
+        prorated_charge,
+        prorated_credit,
+    });
+
+    Ok(())
+}
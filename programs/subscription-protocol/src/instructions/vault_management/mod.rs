@@ -1,3 +1,5 @@
+#![allow(ambiguous_glob_reexports)]
+
 pub mod rebalance_vault;
 pub mod set_emergency_mode;
 
@@ -1,6 +1,6 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
-    token::{Token, TokenAccount},
+    token_interface::{TokenInterface, TokenAccount},
     associated_token::AssociatedToken,
 };
 use crate::{YieldVault, VaultRebalanced, ErrorCodes};
@@ -29,14 +29,14 @@ pub struct RebalanceVault<'info> {
         token::mint = yield_vault.mint,
         constraint = vault_buffer.key() == yield_vault.usdc_buffer
     )]
-    pub vault_buffer: Account<'info, TokenAccount>,
+    pub vault_buffer: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
         token::mint = f_token_mint.key(),
         constraint = jupiter_ftoken_account.key() == yield_vault.jupiter_ftoken_account
     )]
-    pub jupiter_ftoken_account: Account<'info, TokenAccount>,
+    pub jupiter_ftoken_account: InterfaceAccount<'info, TokenAccount>,
 
     // Jupiter Lend accounts
     /// CHECK: Jupiter Lend mint
@@ -85,7 +85,7 @@ pub struct RebalanceVault<'info> {
     /// CHECK: Jupiter Lend lending program
     pub lending_program: AccountInfo<'info>,
 
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
@@ -98,7 +98,7 @@ pub fn handler(ctx: Context<RebalanceVault>) -> Result<()> {
     let buffer_balance = ctx.accounts.vault_buffer.amount;
     let total_value = get_vault_total_value(
         ctx.accounts.lending.clone(),
-        &vault,
+        vault,
         Some(&ctx.accounts.vault_buffer),
         Some(&ctx.accounts.jupiter_ftoken_account),
     )?;
@@ -131,7 +131,7 @@ pub fn handler(ctx: Context<RebalanceVault>) -> Result<()> {
         
         // Withdraw from Jupiter Lend
         withdraw_from_jupiter_lend_internal(
-            &vault,
+            vault,
             &ctx.accounts.jupiter_ftoken_account,
             &ctx.accounts.vault_buffer,
             &jupiter_accounts,
@@ -155,7 +155,7 @@ pub fn handler(ctx: Context<RebalanceVault>) -> Result<()> {
         if excess > min_rebalance {
             // Deposit to Jupiter Lend
             deposit_to_jupiter_lend_internal(
-                &vault,
+                vault,
                 &ctx.accounts.vault_buffer,
                 &ctx.accounts.jupiter_ftoken_account,
                 &jupiter_accounts,
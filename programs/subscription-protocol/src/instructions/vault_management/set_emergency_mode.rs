@@ -1,5 +1,5 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Token, TokenAccount};
+use anchor_spl::token_interface::{TokenInterface, TokenAccount};
 use crate::{YieldVault, EmergencyModeChanged, ErrorCodes};
 use crate::utils::{calculate_current_exchange_rate, get_vault_total_value};
 
@@ -20,14 +20,14 @@ pub struct SetEmergencyMode<'info> {
         token::mint = yield_vault.mint,
         constraint = vault_buffer.key() == yield_vault.usdc_buffer
     )]
-    pub vault_buffer: Account<'info, TokenAccount>,
+    pub vault_buffer: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
         token::mint = f_token_mint.key(),
         constraint = jupiter_ftoken_account.key() == yield_vault.jupiter_ftoken_account
     )]
-    pub jupiter_ftoken_account: Account<'info, TokenAccount>,
+    pub jupiter_ftoken_account: InterfaceAccount<'info, TokenAccount>,
 
     /// CHECK: Jupiter Lend fToken mint
     pub f_token_mint: AccountInfo<'info>,
@@ -35,7 +35,7 @@ pub struct SetEmergencyMode<'info> {
     /// CHECK: Jupiter Lend lending account
     pub jupiter_lending: AccountInfo<'info>,
 
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 pub fn handler(ctx: Context<SetEmergencyMode>, enabled: bool) -> Result<()> {
@@ -49,7 +49,7 @@ pub fn handler(ctx: Context<SetEmergencyMode>, enabled: bool) -> Result<()> {
             vault.total_shares_issued,
             get_vault_total_value(
                 ctx.accounts.jupiter_lending.clone(),
-                &vault,
+                vault,
                 Some(&ctx.accounts.vault_buffer),
                 Some(&ctx.accounts.jupiter_ftoken_account),
             )?,
@@ -0,0 +1,229 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    self, Mint, TokenInterface, TokenAccount, TransferChecked, CloseAccount
+};
+use crate::{SubscriptionWallet, YieldVault, SubscriptionWalletClosed, ErrorCodes};
+use crate::utils::{
+    calculate_usdc_value_of_shares,
+    get_vault_total_value,
+    withdraw_from_vault_internal,
+    withdraw_from_jupiter_lend_internal,
+    JupiterLendAccounts,
+    harvest_withheld_fees,
+    is_native_mint
+};
+
+#[derive(Accounts)]
+pub struct CloseSubscriptionWallet<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"subscription_wallet",
+            subscription_wallet.owner.as_ref(),
+            subscription_wallet.mint.as_ref(),
+            &SubscriptionWallet::index_seed(subscription_wallet.wallet_index)
+        ],
+        bump = subscription_wallet.bump,
+        has_one = owner @ ErrorCodes::UnauthorizedWalletAccess,
+        close = owner
+    )]
+    pub subscription_wallet: Account<'info, SubscriptionWallet>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        token::mint = subscription_wallet.mint,
+        token::authority = subscription_wallet
+    )]
+    pub wallet_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    // Receives the swept balance; native SOL wallets are unwrapped straight to `owner`
+    #[account(
+        mut,
+        token::mint = subscription_wallet.mint,
+        token::authority = owner
+    )]
+    pub user_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    // Optional yield vault accounts (required when the wallet holds yield shares)
+    #[account(
+        mut,
+        seeds = [b"yield_vault", subscription_wallet.mint.as_ref()],
+        bump = yield_vault.bump,
+    )]
+    pub yield_vault: Option<Account<'info, YieldVault>>,
+
+    #[account(mut)]
+    pub vault_buffer: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub jupiter_ftoken_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Jupiter Lend lending account
+    pub jupiter_lending: Option<AccountInfo<'info>>,
+
+    #[account(address = subscription_wallet.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+// remaining_accounts: Jupiter Lend withdraw accounts in `JupiterLendAccounts`
+// field order, needed only when the vault buffer can't cover the redemption
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CloseSubscriptionWallet<'info>>,
+) -> Result<()> {
+    let wallet = &ctx.accounts.subscription_wallet;
+
+    // Expired subscriptions still count until cancel_subscription_wallet closes them
+    require!(
+        !wallet.has_active_subscriptions(),
+        ErrorCodes::WalletHasActiveSubscriptions
+    );
+
+    // Redeem any yield shares back into the wallet first
+    let shares_redeemed = wallet.yield_shares;
+    if shares_redeemed > 0 {
+        let vault = ctx.accounts.yield_vault
+            .as_mut()
+            .ok_or(ErrorCodes::YieldAccountsRequired)?;
+        let vault_buffer = ctx.accounts.vault_buffer
+            .as_ref()
+            .ok_or(ErrorCodes::YieldAccountsRequired)?;
+        let jupiter_ftoken_account = ctx.accounts.jupiter_ftoken_account
+            .as_ref()
+            .ok_or(ErrorCodes::YieldAccountsRequired)?;
+        let jupiter_lending = ctx.accounts.jupiter_lending
+            .as_ref()
+            .ok_or(ErrorCodes::YieldAccountsRequired)?;
+        require!(
+            vault_buffer.key() == vault.usdc_buffer,
+            ErrorCodes::InvalidVaultBuffer
+        );
+        require!(
+            jupiter_ftoken_account.key() == vault.jupiter_ftoken_account,
+            ErrorCodes::InvalidFTokenAccount
+        );
+        require!(
+            jupiter_lending.key() == vault.jupiter_lending,
+            ErrorCodes::InvalidJupiterLendAccount
+        );
+
+        // Shares are worth their part of the buffer and the Jupiter Lend position
+        let usdc_value = calculate_usdc_value_of_shares(
+            shares_redeemed,
+            vault.total_shares_issued,
+            get_vault_total_value(
+                jupiter_lending.clone(),
+                vault,
+                Some(vault_buffer),
+                Some(jupiter_ftoken_account),
+            )?,
+        )?;
+
+        // Pull whatever the buffer can't cover out of Jupiter Lend first
+        if vault_buffer.amount < usdc_value {
+            let shortfall = usdc_value - vault_buffer.amount;
+            let jupiter_accounts = JupiterLendAccounts::from_remaining_accounts(
+                vault,
+                ctx.remaining_accounts,
+            )?;
+
+            withdraw_from_jupiter_lend_internal(
+                vault,
+                jupiter_ftoken_account,
+                vault_buffer,
+                &jupiter_accounts,
+                &ctx.accounts.token_program,
+                shortfall,
+            )?;
+        }
+
+        withdraw_from_vault_internal(
+            vault.to_account_info(),
+            vault,
+            vault_buffer,
+            &ctx.accounts.wallet_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+            usdc_value,
+        )?;
+
+        vault.total_shares_issued = vault.total_shares_issued
+            .checked_sub(shares_redeemed)
+            .ok_or(ErrorCodes::MathOverflow)?;
+        // The redemption includes Jupiter Lend yield, so it can exceed what was deposited
+        vault.total_usdc_deposited = vault.total_usdc_deposited.saturating_sub(usdc_value);
+
+        ctx.accounts.wallet_token_account.reload()?;
+    }
+
+    let wallet = &ctx.accounts.subscription_wallet;
+    let swept_amount = ctx.accounts.wallet_token_account.amount;
+
+    // Create PDA signer seeds
+    let owner_key = wallet.owner;
+    let mint_key = wallet.mint;
+    let index_seed = SubscriptionWallet::index_seed(wallet.wallet_index);
+    let bump = wallet.bump;
+    let seeds = &[
+        b"subscription_wallet",
+        owner_key.as_ref(),
+        mint_key.as_ref(),
+        index_seed.as_slice(),
+        &[bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    // Closing a wSOL account releases the wrapped balance with the rent, so
+    // native SOL wallets only need the close below
+    if swept_amount > 0 && !is_native_mint(&wallet.mint) {
+        let user_token_account = ctx.accounts.user_token_account
+            .as_ref()
+            .ok_or(ErrorCodes::TokenAccountRequired)?;
+
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.wallet_token_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: user_token_account.to_account_info(),
+            authority: wallet.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token_interface::transfer_checked(cpi_ctx, swept_amount, ctx.accounts.mint.decimals)?;
+    }
+
+    // Token-2022 fees withheld on transfers into the wallet block the close
+    harvest_withheld_fees(
+        &ctx.accounts.mint,
+        &ctx.accounts.wallet_token_account,
+        &ctx.accounts.token_program,
+    )?;
+
+    // Return the token account's rent to the owner; the wallet PDA is closed on exit
+    let cpi_accounts = CloseAccount {
+        account: ctx.accounts.wallet_token_account.to_account_info(),
+        destination: ctx.accounts.owner.to_account_info(),
+        authority: wallet.to_account_info(),
+    };
+    token_interface::close_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts,
+        signer,
+    ))?;
+
+    emit!(SubscriptionWalletClosed {
+        wallet_pda: wallet.key(),
+        owner: wallet.owner,
+        mint: wallet.mint,
+        wallet_index: wallet.wallet_index,
+        shares_redeemed,
+        swept_amount,
+    });
+
+    msg!("Subscription Wallet closed for user: {}", wallet.owner);
+
+    Ok(())
+}
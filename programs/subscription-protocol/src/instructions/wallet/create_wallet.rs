@@ -1,8 +1,10 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, Token, TokenAccount};
-use crate::{SubscriptionWallet, SubscriptionWalletCreated};
+use anchor_spl::token_interface::{Mint, TokenInterface, TokenAccount};
+use crate::{SubscriptionWallet, SubscriptionWalletCreated, ErrorCodes};
+use crate::utils::has_transfer_hook;
 
 #[derive(Accounts)]
+#[instruction(wallet_index: u16)]
 pub struct CreateSubscriptionWallet<'info> {
     #[account(
         init,
@@ -11,7 +13,8 @@ pub struct CreateSubscriptionWallet<'info> {
         seeds = [
             b"subscription_wallet",
             user.key().as_ref(),
-            mint.key().as_ref()
+            mint.key().as_ref(),
+            &SubscriptionWallet::index_seed(wallet_index)
         ],
         bump
     )]
@@ -22,17 +25,24 @@ pub struct CreateSubscriptionWallet<'info> {
         constraint = main_token_account.owner == subscription_wallet.key(),
         constraint = main_token_account.mint == mint.key()
     )]
-    pub main_token_account: Account<'info, TokenAccount>,
+    pub main_token_account: InterfaceAccount<'info, TokenAccount>,
 
     #[account(mut)]
     pub user: Signer<'info>,
 
-    pub mint: Account<'info, Mint>,
-    pub token_program: Program<'info, Token>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
-pub fn handler(ctx: Context<CreateSubscriptionWallet>) -> Result<()> {
+pub fn handler(
+    ctx: Context<CreateSubscriptionWallet>,
+    wallet_index: u16,
+    label: String,
+) -> Result<()> {
+    require!(label.len() <= 32, ErrorCodes::WalletLabelTooLong);
+    require!(!has_transfer_hook(&ctx.accounts.mint), ErrorCodes::UnsupportedMintExtension);
+
     let wallet = &mut ctx.accounts.subscription_wallet;
     
     wallet.owner = ctx.accounts.user.key();
@@ -43,11 +53,15 @@ pub fn handler(ctx: Context<CreateSubscriptionWallet>) -> Result<()> {
     wallet.yield_shares = 0;
     wallet.is_yield_enabled = false;
     wallet.bump = ctx.bumps.subscription_wallet;
+    wallet.wallet_index = wallet_index;
+    wallet.label = label;
 
     emit!(SubscriptionWalletCreated {
         wallet_pda: wallet.key(),
         owner: wallet.owner,
         mint: wallet.mint,
+        wallet_index,
+        label: wallet.label.clone(),
     });
 
     msg!("Subscription Wallet created for user: {}", wallet.owner);
@@ -1,6 +1,10 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_lang::system_program;
+use anchor_spl::token_interface::{
+    self, Mint, TokenInterface, TokenAccount, TransferChecked, SyncNative
+};
 use crate::{SubscriptionWallet, WalletDeposit, ErrorCodes};
+use crate::utils::{transfer_fee, is_native_mint};
 
 #[derive(Accounts)]
 pub struct DepositToWallet<'info> {
@@ -8,7 +12,8 @@ pub struct DepositToWallet<'info> {
         seeds = [
             b"subscription_wallet",
             subscription_wallet.owner.as_ref(),
-            subscription_wallet.mint.as_ref()
+            subscription_wallet.mint.as_ref(),
+            &SubscriptionWallet::index_seed(subscription_wallet.wallet_index)
         ],
         bump = subscription_wallet.bump,
     )]
@@ -17,43 +22,85 @@ pub struct DepositToWallet<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
 
+    // Source for token deposits; native SOL wallets take lamports from `user` instead
     #[account(
         mut,
         token::mint = subscription_wallet.mint,
         token::authority = user
     )]
-    pub user_token_account: Account<'info, TokenAccount>,
+    pub user_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
         token::mint = subscription_wallet.mint,
         token::authority = subscription_wallet
     )]
-    pub wallet_token_account: Account<'info, TokenAccount>,
+    pub wallet_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    pub token_program: Program<'info, Token>,
+    #[account(address = subscription_wallet.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    // Required for native SOL wallets
+    pub system_program: Option<Program<'info, System>>,
 }
 
 pub fn handler(ctx: Context<DepositToWallet>, amount: u64) -> Result<()> {
     require!(amount > 0, ErrorCodes::InvalidDepositAmount);
 
-    // Transfer from user's main wallet to subscription wallet
-    let cpi_accounts = Transfer {
-        from: ctx.accounts.user_token_account.to_account_info(),
-        to: ctx.accounts.wallet_token_account.to_account_info(),
-        authority: ctx.accounts.user.to_account_info(),
+    let deposited = if is_native_mint(&ctx.accounts.subscription_wallet.mint) {
+        let system_program = ctx.accounts.system_program
+            .as_ref()
+            .ok_or(ErrorCodes::NativeSolAccountsRequired)?;
+
+        // Wrap: send lamports into the wallet's wSOL account and sync its balance
+        let cpi_accounts = system_program::Transfer {
+            from: ctx.accounts.user.to_account_info(),
+            to: ctx.accounts.wallet_token_account.to_account_info(),
+        };
+        system_program::transfer(
+            CpiContext::new(system_program.to_account_info(), cpi_accounts),
+            amount,
+        )?;
+
+        let cpi_accounts = SyncNative {
+            account: ctx.accounts.wallet_token_account.to_account_info(),
+        };
+        token_interface::sync_native(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+        )?;
+
+        amount
+    } else {
+        let user_token_account = ctx.accounts.user_token_account
+            .as_ref()
+            .ok_or(ErrorCodes::TokenAccountRequired)?;
+
+        // Transfer from user's main wallet to subscription wallet
+        let cpi_accounts = TransferChecked {
+            from: user_token_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.wallet_token_account.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+
+        // The wallet is credited net of any Token-2022 transfer fee
+        amount
+            .checked_sub(transfer_fee(&ctx.accounts.mint, amount)?)
+            .ok_or(ErrorCodes::MathOverflow)?
     };
-    let cpi_program = ctx.accounts.token_program.to_account_info();
-    let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-    token::transfer(cpi_ctx, amount)?;
 
     emit!(WalletDeposit {
         wallet_pda: ctx.accounts.subscription_wallet.key(),
         user: ctx.accounts.subscription_wallet.owner,
-        amount: amount,
+        amount: deposited,
     });
 
-    msg!("Deposited {} tokens to Subscription Wallet", amount);
+    msg!("Deposited {} tokens to Subscription Wallet", deposited);
 
     Ok(())
 }
\ No newline at end of file
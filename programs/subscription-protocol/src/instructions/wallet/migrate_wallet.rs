@@ -0,0 +1,81 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+use crate::{SubscriptionWallet, LegacySubscriptionWallet, SubscriptionWalletMigrated, ErrorCodes};
+use crate::utils::grow_account;
+
+#[derive(Accounts)]
+pub struct MigrateSubscriptionWallet<'info> {
+    /// CHECK: Wallet created before fields were appended after `bump`; address
+    /// is verified here and the legacy layout is deserialized in the handler
+    #[account(
+        mut,
+        seeds = [
+            b"subscription_wallet",
+            owner.key().as_ref(),
+            mint.key().as_ref()
+        ],
+        bump,
+        owner = crate::ID,
+    )]
+    pub subscription_wallet: UncheckedAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<MigrateSubscriptionWallet>) -> Result<()> {
+    let wallet_info = ctx.accounts.subscription_wallet.to_account_info();
+    let full_len = 8 + SubscriptionWallet::INIT_SPACE;
+    require!(wallet_info.data_len() < full_len, ErrorCodes::WalletAlreadyMigrated);
+
+    let legacy = {
+        let data = wallet_info.try_borrow_data()?;
+        require!(
+            data.len() >= 8 && data[..8] == *SubscriptionWallet::DISCRIMINATOR,
+            ErrorCodes::UnauthorizedWalletAccess
+        );
+        LegacySubscriptionWallet::deserialize(&mut &data[8..])?
+    };
+
+    require!(
+        legacy.owner == ctx.accounts.owner.key(),
+        ErrorCodes::UnauthorizedWalletAccess
+    );
+
+    // Legacy wallets live at the index-0 address and have nothing committed
+    // yet; migrate_subscription commits each subscription's buffer
+    let wallet = SubscriptionWallet {
+        owner: legacy.owner,
+        main_token_account: legacy.main_token_account,
+        mint: legacy.mint,
+        total_subscriptions: legacy.total_subscriptions,
+        total_spent: legacy.total_spent,
+        yield_shares: legacy.yield_shares,
+        is_yield_enabled: legacy.is_yield_enabled,
+        bump: legacy.bump,
+        committed_amount: 0,
+        wallet_index: 0,
+        label: String::new(),
+    };
+
+    // The owner pays the rent for the extra space
+    grow_account(
+        &wallet_info,
+        &ctx.accounts.owner.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        full_len,
+    )?;
+    wallet.try_serialize(&mut &mut wallet_info.try_borrow_mut_data()?[..])?;
+
+    emit!(SubscriptionWalletMigrated {
+        wallet_pda: wallet_info.key(),
+        owner: wallet.owner,
+        mint: wallet.mint,
+    });
+
+    Ok(())
+}
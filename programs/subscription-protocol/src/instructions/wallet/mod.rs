@@ -1,7 +1,13 @@
+#![allow(ambiguous_glob_reexports)]
+
 pub mod create_wallet;
 pub mod deposit;
 pub mod withdraw;
+pub mod migrate_wallet;
+pub mod close_wallet;
 
 pub use create_wallet::*;
 pub use deposit::*;
-pub use withdraw::*;
\ No newline at end of file
+pub use withdraw::*;
+pub use migrate_wallet::*;
+pub use close_wallet::*;
\ No newline at end of file
@@ -1,7 +1,9 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
-use crate::{SubscriptionWallet, WalletWithdrawal, ErrorCodes};
-use crate::utils::calculate_committed_balance;
+use anchor_spl::token_interface::{
+    self, Mint, TokenInterface, TokenAccount, TransferChecked, CloseAccount
+};
+use crate::{SubscriptionWallet, YieldVault, WalletWithdrawal, ErrorCodes};
+use crate::utils::{calculate_committed_balance, wallet_yield_value, is_native_mint};
 
 #[derive(Accounts)]
 pub struct WithdrawFromWallet<'info> {
@@ -9,7 +11,8 @@ pub struct WithdrawFromWallet<'info> {
         seeds = [
             b"subscription_wallet",
             subscription_wallet.owner.as_ref(),
-            subscription_wallet.mint.as_ref()
+            subscription_wallet.mint.as_ref(),
+            &SubscriptionWallet::index_seed(subscription_wallet.wallet_index)
         ],
         bump = subscription_wallet.bump,
         has_one = owner @ ErrorCodes::UnauthorizedWalletAccess
@@ -19,67 +22,115 @@ pub struct WithdrawFromWallet<'info> {
     #[account(mut)]
     pub owner: Signer<'info>,
 
+    // Native SOL wallets: a wSOL account that is closed to `owner` to unwrap
     #[account(
         mut,
         token::mint = subscription_wallet.mint,
         token::authority = owner
     )]
-    pub user_token_account: Account<'info, TokenAccount>,
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
         token::mint = subscription_wallet.mint,
         token::authority = subscription_wallet
     )]
-    pub wallet_token_account: Account<'info, TokenAccount>,
+    pub wallet_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    pub token_program: Program<'info, Token>,
+    // Optional yield vault accounts (required when the wallet holds yield shares,
+    // which count towards the committed balance)
+    #[account(
+        seeds = [b"yield_vault", subscription_wallet.mint.as_ref()],
+        bump = yield_vault.bump,
+    )]
+    pub yield_vault: Option<Account<'info, YieldVault>>,
+
+    pub vault_buffer: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub jupiter_ftoken_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Jupiter Lend lending account
+    pub jupiter_lending: Option<AccountInfo<'info>>,
+
+    #[account(address = subscription_wallet.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 pub fn handler(ctx: Context<WithdrawFromWallet>, amount: u64) -> Result<()> {
     let wallet = &ctx.accounts.subscription_wallet;
-    
+
     require!(amount > 0, ErrorCodes::InvalidWithdrawAmount);
 
     // Calculate committed balance (3 months buffer for subscriptions)
     let committed_amount = calculate_committed_balance(
         &ctx.accounts.subscription_wallet,
     )?;
-    
+
+    // Value of yield shares counts towards the committed balance
+    let yield_value = wallet_yield_value(
+        wallet,
+        ctx.accounts.yield_vault.as_ref(),
+        ctx.accounts.vault_buffer.as_ref(),
+        ctx.accounts.jupiter_ftoken_account.as_ref(),
+        ctx.accounts.jupiter_lending.as_ref(),
+    )?;
+
     let available_balance = ctx.accounts.wallet_token_account.amount;
-    let withdrawable = available_balance.saturating_sub(committed_amount);
-    
+    let total_balance = available_balance
+        .checked_add(yield_value)
+        .ok_or(ErrorCodes::MathOverflow)?;
+    let withdrawable = total_balance
+        .saturating_sub(committed_amount)
+        .min(available_balance);
+
     require!(amount <= withdrawable, ErrorCodes::InsufficientAvailableBalance);
 
     // Create PDA signer seeds
     let owner_key = wallet.owner;
     let mint_key = wallet.mint;
+    let index_seed = SubscriptionWallet::index_seed(wallet.wallet_index);
     let bump = wallet.bump;
     let seeds = &[
         b"subscription_wallet",
         owner_key.as_ref(),
         mint_key.as_ref(),
+        index_seed.as_slice(),
         &[bump],
     ];
     let signer = &[&seeds[..]];
 
     // Transfer from wallet to user
-    let cpi_accounts = Transfer {
+    let cpi_accounts = TransferChecked {
         from: ctx.accounts.wallet_token_account.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
         to: ctx.accounts.user_token_account.to_account_info(),
         authority: ctx.accounts.subscription_wallet.to_account_info(),
     };
     let cpi_program = ctx.accounts.token_program.to_account_info();
     let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
-    token::transfer(cpi_ctx, amount)?;
+    token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+
+    // Unwrap: closing the owner's wSOL account releases its lamports to the owner
+    if is_native_mint(&wallet.mint) {
+        let cpi_accounts = CloseAccount {
+            account: ctx.accounts.user_token_account.to_account_info(),
+            destination: ctx.accounts.owner.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        };
+        token_interface::close_account(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+        )?;
+    }
 
     emit!(WalletWithdrawal {
         wallet_pda: wallet.key(),
         user: wallet.owner,
-        amount: amount,
+        amount,
     });
 
     msg!("Withdrawn {} tokens from Subscription Wallet", amount);
 
     Ok(())
-}
\ No newline at end of file
+}
@@ -1,9 +1,10 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_spl::token_interface::{self, Mint, TokenInterface, TokenAccount, TransferChecked};
 use crate::{SubscriptionWallet, YieldVault, YieldDeposit, ErrorCodes};
 use crate::utils::{
     calculate_shares_for_deposit,
-    get_vault_total_value
+    get_vault_total_value,
+    transfer_fee
 };
 
 #[derive(Accounts)]
@@ -13,7 +14,8 @@ pub struct DepositToYield<'info> {
         seeds = [
             b"subscription_wallet",
             subscription_wallet.owner.as_ref(),
-            subscription_wallet.mint.as_ref()
+            subscription_wallet.mint.as_ref(),
+            &SubscriptionWallet::index_seed(subscription_wallet.wallet_index)
         ],
         bump = subscription_wallet.bump,
         has_one = owner @ ErrorCodes::UnauthorizedWalletAccess
@@ -35,19 +37,22 @@ pub struct DepositToYield<'info> {
         token::mint = subscription_wallet.mint,
         token::authority = subscription_wallet
     )]
-    pub wallet_token_account: Account<'info, TokenAccount>,
+    pub wallet_token_account: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
         token::mint = subscription_wallet.mint,
         constraint = vault_buffer.key() == yield_vault.usdc_buffer
     )]
-    pub vault_buffer: Account<'info, TokenAccount>,
+    pub vault_buffer: InterfaceAccount<'info, TokenAccount>,
 
     /// CHECK: Jupiter Lend lending account
     pub jupiter_lending: AccountInfo<'info>,
 
-    pub token_program: Program<'info, Token>,
+    #[account(address = subscription_wallet.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 pub fn handler(ctx: Context<DepositToYield>, amount: u64) -> Result<()> {
@@ -61,13 +66,18 @@ pub fn handler(ctx: Context<DepositToYield>, amount: u64) -> Result<()> {
         ErrorCodes::InsufficientWalletBalance
     );
 
+    // Shares are issued for what reaches the vault after any transfer fee
+    let deposited = amount
+        .checked_sub(transfer_fee(&ctx.accounts.mint, amount)?)
+        .ok_or(ErrorCodes::MathOverflow)?;
+
     // Calculate shares to issue
     let shares_to_issue = calculate_shares_for_deposit(
-        amount,
+        deposited,
         vault.total_shares_issued,
         get_vault_total_value(
             ctx.accounts.jupiter_lending.clone(),
-            &vault,
+            vault,
             Some(&ctx.accounts.vault_buffer),
             None,
         )?,
@@ -76,17 +86,20 @@ pub fn handler(ctx: Context<DepositToYield>, amount: u64) -> Result<()> {
     // Transfer to vault buffer
     let owner_key = wallet.owner;
     let mint_key = wallet.mint;
+    let index_seed = SubscriptionWallet::index_seed(wallet.wallet_index);
     let bump = wallet.bump;
     let seeds = &[
         b"subscription_wallet",
         owner_key.as_ref(),
         mint_key.as_ref(),
+        index_seed.as_slice(),
         &[bump],
     ];
     let signer = &[&seeds[..]];
 
-    let cpi_accounts = Transfer {
+    let cpi_accounts = TransferChecked {
         from: ctx.accounts.wallet_token_account.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
         to: ctx.accounts.vault_buffer.to_account_info(),
         authority: wallet.to_account_info(),
     };
@@ -95,7 +108,7 @@ pub fn handler(ctx: Context<DepositToYield>, amount: u64) -> Result<()> {
         cpi_accounts,
         signer,
     );
-    token::transfer(cpi_ctx, amount)?;
+    token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
 
     // Update state
     wallet.yield_shares = wallet.yield_shares
@@ -106,13 +119,13 @@ pub fn handler(ctx: Context<DepositToYield>, amount: u64) -> Result<()> {
         .checked_add(shares_to_issue)
         .ok_or(ErrorCodes::MathOverflow)?;
     vault.total_usdc_deposited = vault.total_usdc_deposited
-        .checked_add(amount)
+        .checked_add(deposited)
         .ok_or(ErrorCodes::MathOverflow)?;
 
     emit!(YieldDeposit {
         wallet_pda: wallet.key(),
         shares_issued: shares_to_issue,
-        usdc_amount: amount,
+        usdc_amount: deposited,
     });
 
     Ok(())
@@ -1,10 +1,11 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Token, TokenAccount};
+use anchor_spl::token_interface::{Mint, TokenInterface, TokenAccount};
 use crate::{SubscriptionWallet, YieldVault, YieldDisabled, ErrorCodes};
 use crate::utils::{
     calculate_usdc_value_of_shares,
     get_vault_total_value,
-    withdraw_from_vault_internal
+    withdraw_from_vault_internal,
+    transfer_fee
 };
 
 #[derive(Accounts)]
@@ -14,7 +15,8 @@ pub struct DisableYield<'info> {
         seeds = [
             b"subscription_wallet",
             subscription_wallet.owner.as_ref(),
-            subscription_wallet.mint.as_ref()
+            subscription_wallet.mint.as_ref(),
+            &SubscriptionWallet::index_seed(subscription_wallet.wallet_index)
         ],
         bump = subscription_wallet.bump,
         has_one = owner @ ErrorCodes::UnauthorizedWalletAccess
@@ -36,19 +38,22 @@ pub struct DisableYield<'info> {
         token::mint = subscription_wallet.mint,
         token::authority = subscription_wallet
     )]
-    pub wallet_token_account: Account<'info, TokenAccount>,
+    pub wallet_token_account: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
         token::mint = subscription_wallet.mint,
         constraint = vault_buffer.key() == yield_vault.usdc_buffer
     )]
-    pub vault_buffer: Account<'info, TokenAccount>,
+    pub vault_buffer: InterfaceAccount<'info, TokenAccount>,
 
     /// CHECK: Jupiter Lend lending account
     pub jupiter_lending: AccountInfo<'info>,
 
-    pub token_program: Program<'info, Token>,
+    #[account(address = subscription_wallet.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 pub fn handler(ctx: Context<DisableYield>) -> Result<()> {
@@ -64,7 +69,7 @@ pub fn handler(ctx: Context<DisableYield>) -> Result<()> {
         vault.total_shares_issued,
         get_vault_total_value(
             ctx.accounts.jupiter_lending.clone(),
-            &vault,
+            vault,
             Some(&ctx.accounts.vault_buffer),
             None,
         )?,
@@ -73,12 +78,16 @@ pub fn handler(ctx: Context<DisableYield>) -> Result<()> {
     // Withdraw from vault to user's wallet
     withdraw_from_vault_internal(
         vault.to_account_info(),
-        &vault,
+        vault,
         &ctx.accounts.vault_buffer,
         &ctx.accounts.wallet_token_account,
+        &ctx.accounts.mint,
         &ctx.accounts.token_program,
         usdc_value,
     )?;
+    let usdc_received = usdc_value
+        .checked_sub(transfer_fee(&ctx.accounts.mint, usdc_value)?)
+        .ok_or(ErrorCodes::MathOverflow)?;
 
     // Update state
     vault.total_shares_issued = vault.total_shares_issued
@@ -95,7 +104,7 @@ pub fn handler(ctx: Context<DisableYield>) -> Result<()> {
     emit!(YieldDisabled {
         wallet_pda: wallet.key(),
         shares_redeemed,
-        usdc_received: usdc_value,
+        usdc_received,
     });
 
     Ok(())
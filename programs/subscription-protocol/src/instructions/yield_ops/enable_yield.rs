@@ -1,10 +1,11 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_spl::token_interface::{self, Mint, TokenInterface, TokenAccount, TransferChecked};
 use crate::{SubscriptionWallet, YieldVault, YieldEnabled, ErrorCodes};
 use crate::utils::{
     calculate_buffer_amount,
     calculate_shares_for_deposit,
-    get_vault_total_value
+    get_vault_total_value,
+    transfer_fee
 };
 
 #[derive(Accounts)]
@@ -14,7 +15,8 @@ pub struct EnableYield<'info> {
         seeds = [
             b"subscription_wallet",
             subscription_wallet.owner.as_ref(),
-            subscription_wallet.mint.as_ref()
+            subscription_wallet.mint.as_ref(),
+            &SubscriptionWallet::index_seed(subscription_wallet.wallet_index)
         ],
         bump = subscription_wallet.bump,
         has_one = owner @ ErrorCodes::UnauthorizedWalletAccess
@@ -36,19 +38,22 @@ pub struct EnableYield<'info> {
         token::mint = subscription_wallet.mint,
         token::authority = subscription_wallet
     )]
-    pub wallet_token_account: Account<'info, TokenAccount>,
+    pub wallet_token_account: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
         token::mint = subscription_wallet.mint,
         constraint = vault_buffer.key() == yield_vault.usdc_buffer
     )]
-    pub vault_buffer: Account<'info, TokenAccount>,
+    pub vault_buffer: InterfaceAccount<'info, TokenAccount>,
 
     /// CHECK: Jupiter Lend lending account
     pub jupiter_lending: AccountInfo<'info>,
 
-    pub token_program: Program<'info, Token>,
+    #[account(address = subscription_wallet.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 pub fn handler(ctx: Context<EnableYield>, amount: u64) -> Result<()> {
@@ -69,18 +74,23 @@ pub fn handler(ctx: Context<EnableYield>, amount: u64) -> Result<()> {
 
     require!(yield_amount > 0, ErrorCodes::YieldAmountTooSmall);
 
+    // Shares are issued for what reaches the vault after any transfer fee
+    let deposited = yield_amount
+        .checked_sub(transfer_fee(&ctx.accounts.mint, yield_amount)?)
+        .ok_or(ErrorCodes::MathOverflow)?;
+
     // Calculate shares to issue (1:1 initially, will accrue yield over time)
     let shares_to_issue = if vault.total_shares_issued == 0 {
         // First deposit: 1:1 ratio
-        yield_amount
+        deposited
     } else {
         // Subsequent deposits: based on current exchange rate
         calculate_shares_for_deposit(
-            yield_amount,
+            deposited,
             vault.total_shares_issued,
             get_vault_total_value(
                 ctx.accounts.jupiter_lending.clone(),
-                &vault,
+                vault,
                 Some(&ctx.accounts.vault_buffer),
                 None, // fToken account not needed for share calculation during deposit
             )?,
@@ -88,19 +98,22 @@ pub fn handler(ctx: Context<EnableYield>, amount: u64) -> Result<()> {
     };
 
     // Transfer yield portion to vault buffer
-    let cpi_accounts = Transfer {
+    let cpi_accounts = TransferChecked {
         from: ctx.accounts.wallet_token_account.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
         to: ctx.accounts.vault_buffer.to_account_info(),
         authority: wallet.to_account_info(),
     };
     
     let owner_key = wallet.owner;
     let mint_key = wallet.mint;
+    let index_seed = SubscriptionWallet::index_seed(wallet.wallet_index);
     let bump = wallet.bump;
     let seeds = &[
         b"subscription_wallet",
         owner_key.as_ref(),
         mint_key.as_ref(),
+        index_seed.as_slice(),
         &[bump],
     ];
     let signer = &[&seeds[..]];
@@ -110,7 +123,7 @@ pub fn handler(ctx: Context<EnableYield>, amount: u64) -> Result<()> {
         cpi_accounts,
         signer,
     );
-    token::transfer(cpi_ctx, yield_amount)?;
+    token_interface::transfer_checked(cpi_ctx, yield_amount, ctx.accounts.mint.decimals)?;
 
     // Update state
     wallet.yield_shares = shares_to_issue;
@@ -120,17 +133,17 @@ pub fn handler(ctx: Context<EnableYield>, amount: u64) -> Result<()> {
         .checked_add(shares_to_issue)
         .ok_or(ErrorCodes::MathOverflow)?;
     vault.total_usdc_deposited = vault.total_usdc_deposited
-        .checked_add(yield_amount)
+        .checked_add(deposited)
         .ok_or(ErrorCodes::MathOverflow)?;
 
     emit!(YieldEnabled {
         wallet_pda: wallet.key(),
         shares_issued: shares_to_issue,
-        usdc_amount: yield_amount,
+        usdc_amount: deposited,
         buffer_amount,
     });
 
-    msg!("Yield enabled: {} shares issued for {} USDC", shares_to_issue, yield_amount);
+    msg!("Yield enabled: {} shares issued for {} USDC", shares_to_issue, deposited);
 
     Ok(())
 }
\ No newline at end of file
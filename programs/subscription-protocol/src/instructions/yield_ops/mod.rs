@@ -1,3 +1,5 @@
+#![allow(ambiguous_glob_reexports)]
+
 pub mod enable_yield;
 pub mod disable_yield;
 pub mod deposit_to_yield;
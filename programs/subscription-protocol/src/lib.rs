@@ -45,15 +45,32 @@ pub mod subscription_protocol {
         instructions::protocol::update_protocol_fee::handler(ctx, new_fee_bps)
     }
 
+    /// Update keeper reward for cranking payments (admin only)
+    pub fn update_keeper_reward(
+        ctx: Context<UpdateKeeperReward>,
+        keeper_reward: KeeperReward,
+    ) -> Result<()> {
+        instructions::protocol::update_keeper_reward::handler(ctx, keeper_reward)
+    }
+
+    /// Upgrade the protocol config created before keeper rewards (admin only)
+    pub fn migrate_protocol_config(
+        ctx: Context<MigrateProtocolConfig>,
+    ) -> Result<()> {
+        instructions::protocol::migrate_protocol_config::handler(ctx)
+    }
+
     // ========================================================================
     // Subscription Wallet Management
     // ========================================================================
 
-    /// Create a Subscription Wallet (Virtual Card) for a user
+    /// Create a Subscription Wallet (Virtual Card) for a user; index 0 is the default wallet
     pub fn create_subscription_wallet(
         ctx: Context<CreateSubscriptionWallet>,
+        wallet_index: u16,
+        label: String,
     ) -> Result<()> {
-        instructions::wallet::create_wallet::handler(ctx)
+        instructions::wallet::create_wallet::handler(ctx, wallet_index, label)
     }
 
     /// Deposit funds into Subscription Wallet
@@ -72,6 +89,20 @@ pub mod subscription_protocol {
         instructions::wallet::withdraw::handler(ctx, amount)
     }
 
+    /// Redeem yield, sweep the balance to the owner and close the wallet for its rent
+    pub fn close_subscription_wallet<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseSubscriptionWallet<'info>>,
+    ) -> Result<()> {
+        instructions::wallet::close_wallet::handler(ctx)
+    }
+
+    /// Grow a wallet created before sub-wallets to the current layout (index 0)
+    pub fn migrate_subscription_wallet(
+        ctx: Context<MigrateSubscriptionWallet>,
+    ) -> Result<()> {
+        instructions::wallet::migrate_wallet::handler(ctx)
+    }
+
     // ========================================================================
     // Yield Operations
     // ========================================================================
@@ -134,38 +165,233 @@ pub mod subscription_protocol {
     pub fn register_merchant(
         ctx: Context<RegisterMerchant>,
         plan_id: String,
-        plan_name: String,
-        fee_amount: u64,
-        payment_interval_seconds: i64,
+        config: PlanConfig,
+    ) -> Result<()> {
+        instructions::subscription::register_merchant::handler(ctx, plan_id, config)
+    }
+
+    /// Update plan name, fee, interval or trial (applies to new subscribers)
+    pub fn update_merchant_plan(
+        ctx: Context<UpdateMerchantPlan>,
+        update: PlanUpdate,
     ) -> Result<()> {
-        instructions::subscription::register_merchant::handler(
+        instructions::subscription::update_merchant_plan::handler(ctx, update)
+    }
+
+    /// Pause merchant plan - blocks new subscriptions and payments
+    pub fn pause_plan(
+        ctx: Context<PausePlan>,
+    ) -> Result<()> {
+        instructions::subscription::pause_plan::handler(ctx)
+    }
+
+    /// Resume a paused merchant plan
+    pub fn resume_plan(
+        ctx: Context<ResumePlan>,
+    ) -> Result<()> {
+        instructions::subscription::resume_plan::handler(ctx)
+    }
+
+    /// Close merchant plan and reclaim rent (requires zero subscribers)
+    pub fn close_plan(
+        ctx: Context<ClosePlan>,
+    ) -> Result<()> {
+        instructions::subscription::close_plan::handler(ctx)
+    }
+
+    /// Upgrade a plan created before fields were appended to the current layout
+    pub fn migrate_merchant_plan(
+        ctx: Context<MigrateMerchantPlan>,
+    ) -> Result<()> {
+        instructions::subscription::migrate_merchant_plan::handler(ctx)
+    }
+
+    /// Publish a new plan price for existing subscribers (versioned)
+    pub fn publish_price_change(
+        ctx: Context<PublishPriceChange>,
+        new_fee_amount: u64,
+        effective_at: i64,
+        cutoff_at: i64,
+    ) -> Result<()> {
+        instructions::subscription::publish_price_change::handler(
             ctx,
-            plan_id,
-            plan_name,
-            fee_amount,
-            payment_interval_seconds,
+            new_fee_amount,
+            effective_at,
+            cutoff_at,
+        )
+    }
+
+    /// Create a promotion code with a percent-off or amount-off discount
+    pub fn create_coupon(
+        ctx: Context<CreateCoupon>,
+        code: String,
+        discount: CouponDiscount,
+        duration_periods: u16,
+        max_redemptions: u32,
+        expires_at: i64,
+    ) -> Result<()> {
+        instructions::subscription::create_coupon::handler(
+            ctx,
+            code,
+            discount,
+            duration_periods,
+            max_redemptions,
+            expires_at,
         )
     }
 
     /// Subscribe using Subscription Wallet
-    pub fn subscribe_with_wallet(
-        ctx: Context<SubscribeWithWallet>,
+    pub fn subscribe_with_wallet<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SubscribeWithWallet<'info>>,
         session_token: String,
+        subscription_index: u16,
+        quantity: u32,
+    ) -> Result<()> {
+        instructions::subscription::subscribe::handler(ctx, session_token, subscription_index, quantity)
+    }
+
+    /// Move a subscription from the legacy per-merchant PDA to the per-plan PDA
+    pub fn migrate_subscription(
+        ctx: Context<MigrateSubscription>,
+        subscription_index: u16,
+    ) -> Result<()> {
+        instructions::subscription::migrate_subscription::handler(ctx, subscription_index)
+    }
+
+    /// Accept the latest published plan price
+    pub fn accept_price_change(
+        ctx: Context<AcceptPriceChange>
+    ) -> Result<()> {
+        instructions::subscription::accept_price_change::handler(ctx)
+    }
+
+    /// Switch to another plan of the same merchant with prorated charge or credit
+    pub fn change_plan<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ChangePlan<'info>>,
+        new_subscription_index: u16,
+    ) -> Result<()> {
+        instructions::subscription::change_plan::handler(ctx, new_subscription_index)
+    }
+
+    /// Merchant attaches a coupon to an existing subscription
+    pub fn apply_coupon(
+        ctx: Context<ApplyCoupon>
+    ) -> Result<()> {
+        instructions::subscription::apply_coupon::handler(ctx)
+    }
+
+    /// Change the number of seats billed, prorating the current period
+    pub fn update_quantity<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UpdateQuantity<'info>>,
+        new_quantity: u32,
+    ) -> Result<()> {
+        instructions::subscription::update_quantity::handler(ctx, new_quantity)
+    }
+
+    /// Pause billing on a subscription (vacation mode)
+    pub fn pause_subscription(
+        ctx: Context<PauseSubscription>,
+        pause_seconds: i64,
+    ) -> Result<()> {
+        instructions::subscription::pause_subscription::handler(ctx, pause_seconds)
+    }
+
+    /// Resume a paused subscription and shift its billing anchor
+    pub fn resume_subscription(
+        ctx: Context<ResumeSubscription>
     ) -> Result<()> {
-        instructions::subscription::subscribe::handler(ctx, session_token)
+        instructions::subscription::resume_subscription::handler(ctx)
+    }
+
+    /// Set the subscriber's per-charge, total-spend, payment-count and oracle slippage limits
+    pub fn set_spending_limits(
+        ctx: Context<SetSpendingLimits>,
+        max_amount_per_charge: Option<u64>,
+        max_total_spend: Option<u64>,
+        max_payments: Option<u32>,
+        max_price_slippage_bps: Option<u16>,
+    ) -> Result<()> {
+        instructions::subscription::set_spending_limits::handler(
+            ctx,
+            max_amount_per_charge,
+            max_total_spend,
+            max_payments,
+            max_price_slippage_bps,
+        )
+    }
+
+    /// Merchant reports metered usage for the current period
+    pub fn report_usage(
+        ctx: Context<ReportUsage>,
+        units: u64,
+    ) -> Result<()> {
+        instructions::subscription::report_usage::handler(ctx, units)
     }
 
     /// Execute payment - with automatic yield redemption if needed
-    pub fn execute_payment_from_wallet(
-        ctx: Context<ExecutePaymentFromWallet>
+    pub fn execute_payment_from_wallet<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExecutePaymentFromWallet<'info>>
     ) -> Result<()> {
         instructions::subscription::execute_payment::handler(ctx)
     }
 
+    /// Execute due payments for many subscriptions across a merchant's plans in one mint (keeper-callable)
+    pub fn execute_payments_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExecutePaymentsBatch<'info>>
+    ) -> Result<()> {
+        instructions::subscription::execute_payments_batch::handler(ctx)
+    }
+
+    /// Record a payment the wallet couldn't cover (keeper-callable dunning)
+    pub fn record_failed_payment(
+        ctx: Context<RecordFailedPayment>
+    ) -> Result<()> {
+        instructions::subscription::record_failed_payment::handler(ctx)
+    }
+
+    /// Refund part of the latest payment to the subscriber's wallet (merchant-signed)
+    pub fn refund_payment(
+        ctx: Context<RefundPayment>,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::subscription::refund_payment::handler(ctx, amount)
+    }
+
     /// Cancel subscription
     pub fn cancel_subscription_wallet(
         ctx: Context<CancelSubscriptionWallet>
     ) -> Result<()> {
         instructions::subscription::cancel_subscription::handler(ctx)
     }
+
+    /// Merchant ends a subscription with a reason, optionally refunding unused time
+    pub fn terminate_subscription(
+        ctx: Context<TerminateSubscription>,
+        reason: TerminationReason,
+        refund_unused: bool,
+    ) -> Result<()> {
+        instructions::subscription::terminate_subscription::handler(ctx, reason, refund_unused)
+    }
+
+    /// Schedule cancellation at period end (or at a given time)
+    pub fn schedule_cancellation(
+        ctx: Context<ScheduleCancellation>,
+        cancel_at: Option<i64>,
+    ) -> Result<()> {
+        instructions::subscription::schedule_cancellation::handler(ctx, cancel_at)
+    }
+
+    /// Undo a scheduled cancellation before it takes effect
+    pub fn undo_cancellation(
+        ctx: Context<UndoCancellation>
+    ) -> Result<()> {
+        instructions::subscription::undo_cancellation::handler(ctx)
+    }
+
+    /// Close a subscription once its scheduled cancellation has passed (permissionless)
+    pub fn finalize_cancellation(
+        ctx: Context<FinalizeCancellation>
+    ) -> Result<()> {
+        instructions::subscription::finalize_cancellation::handler(ctx)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,99 @@
+use anchor_lang::prelude::*;
+
+/// Discount a coupon takes off each billing period's base fee
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum CouponDiscount {
+    PercentOff { bps: u16 },
+    AmountOff { amount: u64 },
+}
+
+impl CouponDiscount {
+    pub fn is_valid(&self) -> bool {
+        match self {
+            CouponDiscount::PercentOff { bps } => *bps > 0 && *bps <= 10_000,
+            CouponDiscount::AmountOff { amount } => *amount > 0,
+        }
+    }
+
+    /// Discount for one period billed at `period_fee` (never more than the fee)
+    pub fn discount_for(&self, period_fee: u64) -> u64 {
+        match self {
+            CouponDiscount::PercentOff { bps } => {
+                ((period_fee as u128) * (*bps as u128) / 10_000) as u64
+            }
+            CouponDiscount::AmountOff { amount } => (*amount).min(period_fee),
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Coupon {
+    pub merchant: Pubkey,
+
+    pub mint: Pubkey,
+
+    #[max_len(32)]
+    pub code: String,
+
+    pub discount: CouponDiscount,
+
+    /// Billing periods the discount applies to once redeemed
+    pub duration_periods: u16,
+
+    /// 0 = unlimited
+    pub max_redemptions: u32,
+
+    pub redemptions: u32,
+
+    /// 0 = never expires
+    pub expires_at: i64,
+
+    pub bump: u8,
+}
+
+impl Coupon {
+    pub const LEN: usize = 8 + 32 + 32 + 36 + 9 + 2 + 4 + 4 + 8 + 1;
+
+    /// Get the coupon PDA
+    pub fn get_pda(
+        merchant: &Pubkey,
+        mint: &Pubkey,
+        code: &str,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                b"coupon",
+                merchant.as_ref(),
+                mint.as_ref(),
+                code.as_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    pub fn is_redeemable(&self, current_time: i64) -> bool {
+        (self.expires_at == 0 || current_time < self.expires_at)
+            && (self.max_redemptions == 0 || self.redemptions < self.max_redemptions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent_off_discounts_a_share_of_the_fee() {
+        assert_eq!(CouponDiscount::PercentOff { bps: 2_500 }.discount_for(1_000), 250);
+        assert_eq!(CouponDiscount::PercentOff { bps: 10_000 }.discount_for(1_000), 1_000);
+        // Rounds down in the merchant's favour
+        assert_eq!(CouponDiscount::PercentOff { bps: 3_333 }.discount_for(10), 3);
+    }
+
+    #[test]
+    fn amount_off_never_exceeds_the_fee() {
+        assert_eq!(CouponDiscount::AmountOff { amount: 300 }.discount_for(1_000), 300);
+        assert_eq!(CouponDiscount::AmountOff { amount: 3_000 }.discount_for(1_000), 1_000);
+    }
+}
@@ -1,5 +1,238 @@
 use anchor_lang::prelude::*;
 
+/// When a billing period is charged relative to the service it pays for
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum BillingMode {
+    /// First period is charged at subscribe time
+    InAdvance,
+    /// First period is charged one interval after subscribing
+    InArrears,
+}
+
+/// How a payment covering several elapsed periods is settled
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum MissedPeriodPolicy {
+    /// Charge every missed period, up to `max_periods`; older ones are forgiven
+    ChargeAll { max_periods: u16 },
+    /// Charge only the latest period; older ones are forgiven
+    ChargeLatest,
+    /// Charge one period at a time and flag the subscription past-due until caught up
+    MarkPastDue,
+}
+
+impl MissedPeriodPolicy {
+    pub fn is_valid(&self) -> bool {
+        match self {
+            MissedPeriodPolicy::ChargeAll { max_periods } => *max_periods > 0,
+            _ => true,
+        }
+    }
+
+    /// Returns `(periods_to_charge, periods_to_advance)` for `periods_due` elapsed periods
+    pub fn resolve(&self, periods_due: u64) -> (u64, u64) {
+        match self {
+            MissedPeriodPolicy::ChargeAll { max_periods } => {
+                (periods_due.min(*max_periods as u64), periods_due)
+            }
+            MissedPeriodPolicy::ChargeLatest => (1, periods_due),
+            MissedPeriodPolicy::MarkPastDue => (1, 1),
+        }
+    }
+}
+
+/// Retry schedule for payments the subscriber's wallet can't cover
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct DunningConfig {
+    /// How long a subscription may stay past-due before it is suspended
+    pub grace_period_seconds: i64,
+    /// Minimum time between two recorded failed attempts
+    pub retry_interval_seconds: i64,
+    /// Failed retries allowed after the first failure before suspension
+    pub max_retries: u8,
+}
+
+impl DunningConfig {
+    pub fn is_valid(&self) -> bool {
+        self.grace_period_seconds >= 0 && self.retry_interval_seconds >= 0
+    }
+}
+
+/// How money flows back to subscribers on this plan
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct RefundPolicy {
+    /// Refund the unused part of a paid period when a subscriber cancels immediately.
+    /// Paid from the merchant token account, which must approve the plan PDA as delegate.
+    pub prorate_on_cancel: bool,
+    /// Reverse the protocol fee share of refunds: the treasury pays it back,
+    /// with the config authority co-signing. Otherwise the treasury keeps its fee.
+    pub reverse_protocol_fee: bool,
+}
+
+/// Whether subscribers may pause billing, and for how long
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct PausePolicy {
+    pub allow_pause: bool,
+    /// Longest single pause allowed
+    pub max_pause_seconds: i64,
+}
+
+impl PausePolicy {
+    pub fn is_valid(&self) -> bool {
+        !self.allow_pause || self.max_pause_seconds > 0
+    }
+}
+
+/// Usage-based pricing charged on top of the base fee
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct MeteringConfig {
+    /// Price per unit above the included quantity (0 = flat-fee plan)
+    pub unit_price: u64,
+    /// Units covered by the base fee each period
+    pub included_units: u64,
+    /// Most units that can be reported between two charges
+    pub max_units: u64,
+}
+
+impl MeteringConfig {
+    pub fn is_metered(&self) -> bool {
+        self.unit_price > 0
+    }
+
+    pub fn is_valid(&self) -> bool {
+        !self.is_metered() || self.max_units > 0
+    }
+
+    /// Returns `(included_units, overage_amount)` for `units` used over `periods` periods
+    pub fn usage_charge(&self, units: u64, periods: u64) -> Option<(u64, u64)> {
+        if !self.is_metered() {
+            return Some((0, 0));
+        }
+
+        let included = self.included_units.checked_mul(periods)?;
+        let overage = units.saturating_sub(included).checked_mul(self.unit_price)?;
+        Some((included, overage))
+    }
+}
+
+/// Most payees a plan can split each charge across
+pub const MAX_REVENUE_SPLITS: usize = 5;
+
+/// One recipient of a plan's revenue split
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct RevenueSplit {
+    /// Owner of the token account that receives this share
+    pub payee: Pubkey,
+    /// Share of the merchant's proceeds, in basis points
+    pub bps: u16,
+}
+
+impl RevenueSplit {
+    /// Splits are valid when empty (merchant takes everything) or when every
+    /// payee is distinct, has a non-zero share, and the shares sum to 10,000 bps
+    pub fn are_valid(splits: &[RevenueSplit]) -> bool {
+        if splits.is_empty() {
+            return true;
+        }
+        if splits.len() > MAX_REVENUE_SPLITS {
+            return false;
+        }
+
+        let mut total: u32 = 0;
+        for (i, split) in splits.iter().enumerate() {
+            if split.bps == 0 || splits[..i].iter().any(|other| other.payee == split.payee) {
+                return false;
+            }
+            total += split.bps as u32;
+        }
+        total == 10_000
+    }
+}
+
+/// Most mints an oracle-priced plan can settle in
+pub const MAX_PRICE_FEEDS: usize = 4;
+
+/// A mint an oracle-priced plan settles in and the feed that prices it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct MintPriceFeed {
+    pub mint: Pubkey,
+    /// Pyth price account quoting `mint` in the reference currency
+    pub price_feed: Pubkey,
+}
+
+/// Prices a plan in a reference currency (e.g. USD) and settles each charge
+/// in the subscriber wallet's mint at the oracle price read when it is made
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct OraclePricing {
+    /// Mints subscribers can pay in, starting with the plan's own mint
+    #[max_len(MAX_PRICE_FEEDS)]
+    pub price_feeds: Vec<MintPriceFeed>,
+    /// Oldest publish time accepted, in seconds
+    pub max_staleness_seconds: i64,
+    /// Widest confidence interval accepted, in basis points of the price
+    pub max_confidence_bps: u16,
+}
+
+impl OraclePricing {
+    /// Checks the bounds, and that the feeds cover the plan's mint and list
+    /// each mint once
+    pub fn is_valid(&self, plan_mint: &Pubkey) -> bool {
+        let distinct_mints = self.price_feeds
+            .iter()
+            .enumerate()
+            .all(|(i, feed)| self.price_feeds[..i].iter().all(|other| other.mint != feed.mint));
+
+        self.max_staleness_seconds > 0
+            && self.max_confidence_bps > 0
+            && self.max_confidence_bps <= 10_000
+            && self.price_feeds.len() <= MAX_PRICE_FEEDS
+            && distinct_mints
+            && self.feed_for(plan_mint).is_some()
+    }
+
+    /// Price feed for `mint`, if the plan settles in it
+    pub fn feed_for(&self, mint: &Pubkey) -> Option<Pubkey> {
+        self.price_feeds
+            .iter()
+            .find(|feed| feed.mint == *mint)
+            .map(|feed| feed.price_feed)
+    }
+}
+
+/// Settings a plan is registered with
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PlanConfig {
+    pub plan_name: String,
+    pub fee_amount: u64,
+    pub payment_interval_seconds: i64,
+    pub trial_period_seconds: i64,
+    pub billing_mode: BillingMode,
+    pub missed_period_policy: MissedPeriodPolicy,
+    pub dunning: DunningConfig,
+    pub refund_policy: RefundPolicy,
+    pub pause_policy: PausePolicy,
+    pub metering: MeteringConfig,
+    pub revenue_splits: Vec<RevenueSplit>,
+    pub unwrap_sol: bool,
+    pub oracle_pricing: Option<OraclePricing>,
+}
+
+/// Plan settings to change; `None` leaves a setting as it is
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct PlanUpdate {
+    pub plan_name: Option<String>,
+    pub fee_amount: Option<u64>,
+    pub payment_interval_seconds: Option<i64>,
+    pub trial_period_seconds: Option<i64>,
+    pub missed_period_policy: Option<MissedPeriodPolicy>,
+    pub dunning: Option<DunningConfig>,
+    pub refund_policy: Option<RefundPolicy>,
+    pub pause_policy: Option<PausePolicy>,
+    pub metering: Option<MeteringConfig>,
+    pub revenue_splits: Option<Vec<RevenueSplit>>,
+    pub unwrap_sol: Option<bool>,
+    pub oracle_pricing: Option<OraclePricing>,
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct MerchantPlan {    
@@ -17,10 +250,53 @@ pub struct MerchantPlan {
     pub is_active: bool,
     pub total_subscribers: u32,
     pub bump: u8,
+
+    // Fields below were appended after the first deployment; plans created
+    // before them are brought up to date with migrate_merchant_plan
+
+    // Versioned price change (version 0 = original price)
+    pub price_version: u32,
+    pub pending_fee_amount: u64,
+    pub price_effective_at: i64,
+    pub price_cutoff_at: i64,
+
+    pub trial_period_seconds: i64,
+    pub billing_mode: BillingMode,
+    pub missed_period_policy: MissedPeriodPolicy,
+    pub dunning: DunningConfig,
+    pub refund_policy: RefundPolicy,
+    pub pause_policy: PausePolicy,
+    pub metering: MeteringConfig,
+
+    /// Payees sharing the merchant's proceeds (empty = all to the merchant)
+    #[max_len(MAX_REVENUE_SPLITS)]
+    pub revenue_splits: Vec<RevenueSplit>,
+
+    /// Native SOL plans only: execute_payment_from_wallet pays the merchant
+    /// unwrapped SOL instead of wSOL. Revenue split payees always receive wSOL.
+    pub unwrap_sol: bool,
+
+    /// When set, fee_amount, pending_fee_amount, the metered unit price and
+    /// amount-off coupons are in reference units with REFERENCE_DECIMALS decimals
+    pub oracle_pricing: Option<OraclePricing>,
+}
+
+/// MerchantPlan as first deployed, before any field was appended after `bump`
+#[derive(AnchorDeserialize)]
+pub struct LegacyMerchantPlan {
+    pub merchant: Pubkey,
+    pub mint: Pubkey,
+    pub plan_id: String,
+    pub plan_name: String,
+    pub fee_amount: u64,
+    pub payment_interval: i64,
+    pub is_active: bool,
+    pub total_subscribers: u32,
+    pub bump: u8,
 }
 
 impl MerchantPlan {
-    pub const LEN: usize = 8 + 64 + 36 + 68 + 8 + 8 + 1 + 4 + 1;
+    pub const LEN: usize = 8 + 64 + 36 + 68 + 8 + 8 + 1 + 4 + 1 + 4 + 24 + 8 + 1 + 3 + 17 + 2 + 9 + 24 + 174 + 1 + 43;
 
     /// Get the merchant plan PDA
     pub fn get_pda(
@@ -40,7 +316,142 @@ impl MerchantPlan {
         )
     }
 
+    pub fn has_trial(&self) -> bool {
+        self.trial_period_seconds > 0
+    }
+
     pub fn is_payment_due(&self, last_payment_timestamp: i64, current_time: i64) -> bool {
         current_time >= last_payment_timestamp + self.payment_interval
     }
-}
\ No newline at end of file
+
+    /// A published price change whose grandfathering window hasn't ended yet
+    pub fn has_pending_price_change(&self, current_time: i64) -> bool {
+        self.price_version > 0 && current_time < self.price_cutoff_at
+    }
+
+    /// Fee charged to new subscribers at `current_time`
+    pub fn current_fee(&self, current_time: i64) -> u64 {
+        if self.price_version > 0 && current_time >= self.price_effective_at {
+            self.pending_fee_amount
+        } else {
+            self.fee_amount
+        }
+    }
+
+    pub fn is_oracle_priced(&self) -> bool {
+        self.oracle_pricing.is_some()
+    }
+
+    /// Whether subscribers can pay in `mint`; oracle-priced plans settle in any
+    /// mint they have a feed for, other plans only in their own mint
+    pub fn settles_in(&self, mint: &Pubkey) -> bool {
+        match self.oracle_pricing.as_ref() {
+            Some(pricing) => pricing.feed_for(mint).is_some(),
+            None => *mint == self.mint,
+        }
+    }
+
+    pub fn has_revenue_splits(&self) -> bool {
+        !self.revenue_splits.is_empty()
+    }
+
+    /// Divide the merchant's proceeds across the revenue splits, in split order.
+    /// Rounding dust goes to the first payee so the shares always add up to `amount`.
+    pub fn split_shares(&self, amount: u64) -> Vec<u64> {
+        let mut shares: Vec<u64> = self.revenue_splits
+            .iter()
+            .map(|split| (amount as u128 * split.bps as u128 / 10_000) as u64)
+            .collect();
+
+        let distributed: u64 = shares.iter().sum();
+        if let Some(first) = shares.first_mut() {
+            *first += amount - distributed;
+        }
+        shares
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan() -> MerchantPlan {
+        // All-zero account data decodes to an empty plan
+        MerchantPlan::deserialize(&mut &vec![0u8; MerchantPlan::INIT_SPACE][..]).unwrap()
+    }
+
+    fn pricing(mints: &[Pubkey]) -> OraclePricing {
+        OraclePricing {
+            price_feeds: mints
+                .iter()
+                .map(|&mint| MintPriceFeed { mint, price_feed: Pubkey::new_unique() })
+                .collect(),
+            max_staleness_seconds: 60,
+            max_confidence_bps: 100,
+        }
+    }
+
+    #[test]
+    fn resolve_applies_missed_period_policy() {
+        assert_eq!(MissedPeriodPolicy::ChargeAll { max_periods: 3 }.resolve(2), (2, 2));
+        assert_eq!(MissedPeriodPolicy::ChargeAll { max_periods: 3 }.resolve(5), (3, 5));
+        assert_eq!(MissedPeriodPolicy::ChargeLatest.resolve(5), (1, 5));
+        assert_eq!(MissedPeriodPolicy::MarkPastDue.resolve(5), (1, 1));
+    }
+
+    #[test]
+    fn usage_charge_bills_units_above_included() {
+        let metering = MeteringConfig { unit_price: 10, included_units: 100, max_units: 1_000 };
+        assert_eq!(metering.usage_charge(80, 1), Some((100, 0)));
+        assert_eq!(metering.usage_charge(150, 1), Some((100, 500)));
+        // Each charged period includes its own allowance
+        assert_eq!(metering.usage_charge(150, 2), Some((200, 0)));
+        assert_eq!(metering.usage_charge(u64::MAX, 1), None);
+    }
+
+    #[test]
+    fn usage_charge_is_zero_for_flat_fee_plans() {
+        let metering = MeteringConfig { unit_price: 0, included_units: 100, max_units: 0 };
+        assert_eq!(metering.usage_charge(1_000, 1), Some((0, 0)));
+    }
+
+    #[test]
+    fn split_shares_gives_rounding_dust_to_first_payee() {
+        let mut plan = plan();
+        plan.revenue_splits = vec![
+            RevenueSplit { payee: Pubkey::new_unique(), bps: 3_333 },
+            RevenueSplit { payee: Pubkey::new_unique(), bps: 3_333 },
+            RevenueSplit { payee: Pubkey::new_unique(), bps: 3_334 },
+        ];
+
+        assert_eq!(plan.split_shares(100), vec![34, 33, 33]);
+        assert_eq!(plan.split_shares(0), vec![0, 0, 0]);
+        assert_eq!(plan.split_shares(u64::MAX).iter().map(|&share| share as u128).sum::<u128>(), u64::MAX as u128);
+    }
+
+    #[test]
+    fn oracle_pricing_must_cover_plan_mint_once() {
+        let (plan_mint, other_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        assert!(pricing(&[plan_mint, other_mint]).is_valid(&plan_mint));
+        assert!(!pricing(&[other_mint]).is_valid(&plan_mint));
+        assert!(!pricing(&[plan_mint, plan_mint]).is_valid(&plan_mint));
+
+        let mut mints = vec![plan_mint];
+        mints.extend((0..MAX_PRICE_FEEDS).map(|_| Pubkey::new_unique()));
+        assert!(!pricing(&mints).is_valid(&plan_mint));
+    }
+
+    #[test]
+    fn settles_in_follows_pricing() {
+        let mut plan = plan();
+        plan.mint = Pubkey::new_unique();
+        let other_mint = Pubkey::new_unique();
+
+        assert!(plan.settles_in(&plan.mint));
+        assert!(!plan.settles_in(&other_mint));
+
+        plan.oracle_pricing = Some(pricing(&[plan.mint, other_mint]));
+        assert!(plan.settles_in(&other_mint));
+        assert!(!plan.settles_in(&Pubkey::new_unique()));
+    }
+}
@@ -4,10 +4,14 @@ pub mod subscription_wallet;
 pub mod merchant_plan;
 pub mod subscription_state;
 pub mod session_token;
+pub mod trial_usage;
+pub mod coupon;
 
 pub use protocol_config::*;
 pub use yield_vault::*;
 pub use subscription_wallet::*;
 pub use merchant_plan::*;
 pub use subscription_state::*;
-pub use session_token::*;
\ No newline at end of file
+pub use session_token::*;
+pub use trial_usage::*;
+pub use coupon::*;
\ No newline at end of file
@@ -1,5 +1,24 @@
 use anchor_lang::prelude::*;
 
+/// Reward paid to whoever cranks a payment, carved out of the protocol fee
+/// The config is shared by every mint, so the reward is a share of the charge
+/// rather than a token amount
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum KeeperReward {
+    Disabled,
+    /// Basis points of the charged amount
+    Bps { bps: u16 },
+}
+
+impl KeeperReward {
+    pub fn is_valid(&self) -> bool {
+        match self {
+            KeeperReward::Bps { bps } => *bps <= 10_000,
+            KeeperReward::Disabled => true,
+        }
+    }
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct ProtocolConfig {
@@ -7,10 +26,23 @@ pub struct ProtocolConfig {
     pub treasury: Pubkey,
     pub protocol_fee_bps: u16,
     pub bump: u8,
+
+    // Appended after the first deployment; migrate_protocol_config brings the
+    // existing singleton up to date
+    pub keeper_reward: KeeperReward,
+}
+
+/// ProtocolConfig as first deployed, before `keeper_reward` was appended
+#[derive(AnchorDeserialize)]
+pub struct LegacyProtocolConfig {
+    pub authority: Pubkey,
+    pub treasury: Pubkey,
+    pub protocol_fee_bps: u16,
+    pub bump: u8,
 }
 
 impl ProtocolConfig {
-    pub const LEN: usize = 8 + 32 + 32 + 2 + 1;
+    pub const LEN: usize = 8 + 32 + 32 + 2 + 1 + 3;
 
     pub fn get_pda(program_id: &Pubkey) -> (Pubkey, u8) {
         Pubkey::find_program_address(
@@ -25,4 +57,17 @@ impl ProtocolConfig {
             .checked_div(10_000)
             .unwrap_or(0) as u64
     }
+
+    /// Keeper's share of a charge, never more than the protocol fee it comes from
+    pub fn calculate_keeper_reward(&self, amount: u64, protocol_fee: u64) -> u64 {
+        let reward = match self.keeper_reward {
+            KeeperReward::Disabled => 0,
+            KeeperReward::Bps { bps } => (amount as u128)
+                .saturating_mul(bps as u128)
+                .checked_div(10_000)
+                .unwrap_or(0) as u64,
+        };
+
+        reward.min(protocol_fee)
+    }
 }
\ No newline at end of file
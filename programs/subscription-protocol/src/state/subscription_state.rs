@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use crate::{BillingMode, MeteringConfig, CouponDiscount, ExpiryReason, ErrorCodes};
 
 #[account]
 #[derive(InitSpace)]
@@ -19,13 +20,120 @@ pub struct SubscriptionState {
     pub session_token: String,
 
     pub bump: u8,
+
+    // Fields below were appended after the first deployment; subscriptions
+    // created before them are moved over with migrate_subscription
+
+    // Wallet buffer reserved for this subscription
+    pub committed_amount: u64,
+
+    pub subscription_index: u16,
+
+    // End of the free trial (0 when the subscription had none)
+    pub trial_ends_at: i64,
+
+    // Dunning state
+    pub is_past_due: bool,
+    pub past_due_since: i64,
+    pub failed_payment_attempts: u8,
+    pub last_failed_attempt_at: i64,
+    pub is_suspended: bool,
+
+    // Plan price version this subscription is billed at
+    pub price_version: u32,
+    pub accepted_price_version: u32,
+    pub accepted_fee_amount: u64,
+    pub price_action_required: bool,
+
+    // Scheduled cancellation (0 when none is scheduled)
+    pub cancel_at_period_end: bool,
+    pub scheduled_cancel_at: i64,
+
+    // Proration credit from plan changes, applied to upcoming charges
+    pub proration_credit: u64,
+
+    // Total refunded by the merchant, never more than total_paid
+    pub total_refunded: u64,
+
+    // Vacation mode (0 when not paused)
+    pub paused_at: i64,
+    pub paused_until: i64,
+
+    // Subscriber-set spending limits (0 = no limit)
+    pub max_amount_per_charge: u64,
+    pub max_total_spend: u64,
+    pub max_payments: u32,
+    pub expired_at: i64,
+
+    // Metered units reported since the last charge
+    pub period_usage: u64,
+
+    // Seats billed; fee_amount is the price per seat
+    pub quantity: u32,
+
+    // Redeemed coupon and the billing periods it still discounts
+    pub coupon: Option<Pubkey>,
+    pub coupon_discount: Option<CouponDiscount>,
+    pub coupon_periods_remaining: u16,
+
+    // Oracle-priced plans: widest gap between the spot and EMA price the
+    // subscriber accepts a charge at, in basis points (0 = no limit)
+    pub max_price_slippage_bps: u16,
+
+    // When the accepted price version starts applying to charges
+    pub accepted_effective_at: i64,
+
+    // Metering rates from the plan at subscribe time; later plan changes only
+    // reach the subscription through change_plan
+    pub metering: MeteringConfig,
+
+    // Most recent payment and how much of it the merchant has refunded;
+    // refund_payment is capped against it
+    pub last_charge_amount: u64,
+    pub last_charge_refunded: u64,
+}
+
+/// SubscriptionState as first deployed, before any field was appended after `bump`
+#[derive(AnchorDeserialize)]
+pub struct LegacySubscriptionState {
+    pub user: Pubkey,
+    pub subscription_wallet: Pubkey,
+    pub merchant: Pubkey,
+    pub mint: Pubkey,
+    pub merchant_plan: Pubkey,
+    pub fee_amount: u64,
+    pub payment_interval: i64,
+    pub last_payment_timestamp: i64,
+    pub total_paid: u64,
+    pub payment_count: u32,
+    pub is_active: bool,
+    pub session_token: String,
+    pub bump: u8,
 }
 
 impl SubscriptionState {
-    pub const LEN: usize = 8 + 160 + 8 + 24 + 4 + 1 + 68 + 1;
+    pub const LEN: usize = 8 + 160 + 8 + 24 + 4 + 1 + 68 + 1 + 8 + 2 + 8 + 19 + 25 + 9 + 8 + 8 + 16 + 28 + 8 + 4 + 45 + 2 + 24 + 16;
 
     /// Get the subscription state PDA
     pub fn get_pda(
+        user: &Pubkey,
+        merchant_plan: &Pubkey,
+        subscription_index: u16,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                b"subscription",
+                user.as_ref(),
+                merchant_plan.as_ref(),
+                &subscription_index.to_le_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    /// Get the pre-migration PDA (one subscription per user, merchant and mint)
+    pub fn get_legacy_pda(
         user: &Pubkey,
         merchant: &Pubkey,
         mint: &Pubkey,
@@ -42,11 +150,273 @@ impl SubscriptionState {
         )
     }
 
+    /// Scale a per-seat fee by the subscription's quantity
+    pub fn scaled_fee(&self, fee_amount: u64) -> Option<u64> {
+        fee_amount.checked_mul(self.quantity as u64)
+    }
+
+    /// Amount billed for one full period
+    pub fn period_fee(&self) -> Option<u64> {
+        self.scaled_fee(self.fee_amount)
+    }
+
+    /// Attach a redeemed coupon, replacing any current one
+    pub fn apply_coupon(&mut self, coupon: Pubkey, discount: CouponDiscount, periods: u16) {
+        self.coupon = Some(coupon);
+        self.coupon_discount = Some(discount);
+        self.coupon_periods_remaining = periods;
+    }
+
+    /// Count discounted periods against the coupon, dropping it once used up
+    pub fn consume_coupon(&mut self, periods: u64) {
+        let remaining = (self.coupon_periods_remaining as u64).saturating_sub(periods);
+        self.coupon_periods_remaining = remaining as u16;
+        if remaining == 0 {
+            self.coupon = None;
+            self.coupon_discount = None;
+        }
+    }
+
+    /// Still inside a free trial that hasn't been converted by a first charge
+    pub fn is_in_trial(&self) -> bool {
+        self.trial_ends_at > 0 && self.payment_count == 0
+    }
+
+    /// First charge lands when the trial ends, later ones one interval apart
+    pub fn next_payment_due(&self) -> i64 {
+        if self.is_in_trial() {
+            self.trial_ends_at
+        } else {
+            self.last_payment_timestamp + self.payment_interval
+        }
+    }
+
     pub fn is_payment_due(&self, current_time: i64) -> bool {
-        current_time >= self.last_payment_timestamp + self.payment_interval
+        current_time >= self.next_payment_due()
+    }
+
+    /// Number of billing periods that have come due and are unpaid
+    pub fn periods_due(&self, current_time: i64) -> u64 {
+        if !self.is_payment_due(current_time) || self.payment_interval <= 0 {
+            return 0;
+        }
+
+        let overdue = (current_time - self.next_payment_due()) / self.payment_interval;
+        (overdue as u64).saturating_add(1)
+    }
+
+    /// Due periods that may still be charged before a scheduled cancellation
+    /// In-arrears charges bill the period that just ended, so the one covering
+    /// the cancellation time is still owed
+    pub fn billable_periods(&self, current_time: i64, billing_mode: BillingMode) -> u64 {
+        let periods_due = self.periods_due(current_time);
+        if self.scheduled_cancel_at == 0 || periods_due == 0 {
+            return periods_due;
+        }
+
+        let cutoff = if billing_mode == BillingMode::InArrears && !self.is_in_trial() {
+            self.scheduled_cancel_at.saturating_add(self.payment_interval)
+        } else {
+            self.scheduled_cancel_at
+        };
+
+        let next_due = self.next_payment_due();
+        if next_due >= cutoff {
+            return 0;
+        }
+
+        let billable = ((cutoff - next_due - 1) / self.payment_interval) as u64 + 1;
+        billable.min(periods_due)
+    }
+
+    /// Amount paid that hasn't been refunded yet
+    pub fn refundable_amount(&self) -> u64 {
+        self.total_paid.saturating_sub(self.total_refunded)
+    }
+
+    /// Part of the most recent payment that hasn't been refunded yet
+    pub fn last_charge_refundable(&self) -> u64 {
+        self.last_charge_amount
+            .saturating_sub(self.last_charge_refunded)
+            .min(self.refundable_amount())
+    }
+
+    /// Spending limit already used up, if any
+    pub fn limit_reached(&self) -> Option<ExpiryReason> {
+        if self.max_payments > 0 && self.payment_count >= self.max_payments {
+            Some(ExpiryReason::MaxPayments)
+        } else if self.max_total_spend > 0 && self.total_paid >= self.max_total_spend {
+            Some(ExpiryReason::MaxTotalSpend)
+        } else {
+            None
+        }
+    }
+
+    pub fn exceeds_charge_limit(&self, amount: u64) -> bool {
+        self.max_amount_per_charge > 0 && amount > self.max_amount_per_charge
+    }
+
+    pub fn exceeds_price_slippage(&self, slippage_bps: u64) -> bool {
+        self.max_price_slippage_bps > 0 && slippage_bps > self.max_price_slippage_bps as u64
+    }
+
+    pub fn exceeds_total_spend(&self, amount: u64) -> bool {
+        self.max_total_spend > 0
+            && self.total_paid.saturating_add(amount) > self.max_total_spend
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expired_at > 0
+    }
+
+    /// A pause has been recorded and not yet cleared, even if it has run out
+    pub fn has_pause(&self) -> bool {
+        self.paused_at > 0
+    }
+
+    /// Billing is paused; a pause that has run out no longer counts
+    pub fn is_paused(&self, current_time: i64) -> bool {
+        self.has_pause() && current_time < self.paused_until
+    }
+
+    /// Clear the pause and shift the billing anchor forward by the time spent
+    /// paused, never more than the pause that was granted
+    ///
+    /// Returns the seconds the anchor moved
+    pub fn end_pause(&mut self, current_time: i64) -> Result<i64> {
+        let paused_seconds = current_time
+            .min(self.paused_until)
+            .checked_sub(self.paused_at)
+            .ok_or(ErrorCodes::MathOverflow)?;
+
+        self.last_payment_timestamp = self.last_payment_timestamp
+            .checked_add(paused_seconds)
+            .ok_or(ErrorCodes::MathOverflow)?;
+        if self.is_in_trial() {
+            self.trial_ends_at = self.trial_ends_at
+                .checked_add(paused_seconds)
+                .ok_or(ErrorCodes::MathOverflow)?;
+        }
+        if self.cancel_at_period_end {
+            self.scheduled_cancel_at = self.next_payment_due();
+        }
+
+        self.paused_at = 0;
+        self.paused_until = 0;
+        Ok(paused_seconds)
+    }
+
+    pub fn has_scheduled_cancellation(&self) -> bool {
+        self.scheduled_cancel_at > 0
+    }
+
+    /// Billing anchor after settling `periods` periods starting at the next due date
+    pub fn anchor_after(&self, periods: u64) -> Option<i64> {
+        let extra_periods = i64::try_from(periods.checked_sub(1)?).ok()?;
+        self.payment_interval
+            .checked_mul(extra_periods)?
+            .checked_add(self.next_payment_due())
+    }
+
+    /// Clear failed-attempt tracking and suspension, and recompute past-due
+    /// from outstanding periods
+    pub fn reset_dunning(&mut self, current_time: i64, billing_mode: BillingMode) {
+        self.is_suspended = false;
+        self.is_past_due = self.billable_periods(current_time, billing_mode) > 0;
+        self.past_due_since = if self.is_past_due { self.next_payment_due() } else { 0 };
+        self.failed_payment_attempts = 0;
+        self.last_failed_attempt_at = 0;
     }
 
     pub fn time_until_next_payment(&self, current_time: i64) -> i64 {
-        (self.last_payment_timestamp + self.payment_interval) - current_time
+        self.next_payment_due() - current_time
+    }
+}
+#[cfg(test)]
+impl SubscriptionState {
+    /// Active single-seat subscription paid once at time 0; everything else zeroed
+    pub(crate) fn for_test(fee_amount: u64, payment_interval: i64) -> Self {
+        // All-zero account data decodes to an empty subscription
+        let mut subscription = Self::deserialize(&mut &vec![0u8; Self::INIT_SPACE][..]).unwrap();
+        subscription.fee_amount = fee_amount;
+        subscription.payment_interval = payment_interval;
+        subscription.payment_count = 1;
+        subscription.is_active = true;
+        subscription.quantity = 1;
+        subscription
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limit_reached_checks_payment_count_then_total_spend() {
+        let mut subscription = SubscriptionState::for_test(100, 30);
+        assert!(subscription.limit_reached().is_none());
+
+        subscription.max_total_spend = 500;
+        subscription.total_paid = 499;
+        assert!(subscription.limit_reached().is_none());
+        subscription.total_paid = 500;
+        assert_eq!(subscription.limit_reached(), Some(ExpiryReason::MaxTotalSpend));
+
+        subscription.max_payments = 1;
+        assert_eq!(subscription.limit_reached(), Some(ExpiryReason::MaxPayments));
+    }
+
+    #[test]
+    fn exceeds_charge_limit_ignores_zero_limit() {
+        let mut subscription = SubscriptionState::for_test(100, 30);
+        assert!(!subscription.exceeds_charge_limit(u64::MAX));
+
+        subscription.max_amount_per_charge = 100;
+        assert!(!subscription.exceeds_charge_limit(100));
+        assert!(subscription.exceeds_charge_limit(101));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn exceeds_total_spend_counts_what_was_paid() {
+        let mut subscription = SubscriptionState::for_test(100, 30);
+        assert!(!subscription.exceeds_total_spend(u64::MAX));
+
+        subscription.max_total_spend = 300;
+        subscription.total_paid = 200;
+        assert!(!subscription.exceeds_total_spend(100));
+        assert!(subscription.exceeds_total_spend(101));
+
+        subscription.total_paid = u64::MAX;
+        assert!(subscription.exceeds_total_spend(1));
+    }
+
+    #[test]
+    fn exceeds_price_slippage_ignores_zero_limit() {
+        let mut subscription = SubscriptionState::for_test(100, 30);
+        assert!(!subscription.exceeds_price_slippage(u64::MAX));
+
+        subscription.max_price_slippage_bps = 50;
+        assert!(!subscription.exceeds_price_slippage(50));
+        assert!(subscription.exceeds_price_slippage(51));
+    }
+
+    #[test]
+    fn last_charge_refundable_is_capped_by_the_latest_payment() {
+        let mut subscription = SubscriptionState::for_test(100, 30);
+        subscription.total_paid = 300;
+        subscription.last_charge_amount = 100;
+        assert_eq!(subscription.last_charge_refundable(), 100);
+
+        subscription.last_charge_refunded = 40;
+        assert_eq!(subscription.last_charge_refundable(), 60);
+
+        // Earlier refunds, e.g. before a migration, still cap the total
+        subscription.total_refunded = 260;
+        assert_eq!(subscription.last_charge_refundable(), 40);
+    }
+
+    #[test]
+    fn len_matches_init_space() {
+        assert_eq!(SubscriptionState::LEN, 8 + SubscriptionState::INIT_SPACE);
+    }
+}
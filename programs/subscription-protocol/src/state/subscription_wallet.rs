@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use crate::ErrorCodes;
 
 #[account]
 #[derive(InitSpace)]
@@ -11,26 +12,82 @@ pub struct SubscriptionWallet {
     pub yield_shares: u64,
     pub is_yield_enabled: bool,
     pub bump: u8,
+
+    // Balance reserved for active subscriptions, not withdrawable
+    pub committed_amount: u64,
+
+    // Sub-wallet of the owner for this mint (0 = the original wallet)
+    pub wallet_index: u16,
+    #[max_len(32)]
+    pub label: String,
+}
+
+/// SubscriptionWallet as first deployed, before any field was appended after `bump`
+#[derive(AnchorDeserialize)]
+pub struct LegacySubscriptionWallet {
+    pub owner: Pubkey,
+    pub main_token_account: Pubkey,
+    pub mint: Pubkey,
+    pub total_subscriptions: u32,
+    pub total_spent: u64,
+    pub yield_shares: u64,
+    pub is_yield_enabled: bool,
+    pub bump: u8,
 }
 
 impl SubscriptionWallet {
-    pub const LEN: usize = 8 + 96 + 4 + 16 + 1 + 1;
+    pub const LEN: usize = 8 + 96 + 4 + 16 + 1 + 1 + 8 + 2 + 36;
 
-    pub fn get_pda(owner: &Pubkey, mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    pub fn get_pda(
+        owner: &Pubkey,
+        mint: &Pubkey,
+        wallet_index: u16,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
         Pubkey::find_program_address(
             &[
                 b"subscription_wallet",
                 owner.as_ref(),
                 mint.as_ref(),
+                &Self::index_seed(wallet_index),
             ],
             program_id,
         )
     }
 
+    /// PDA seed for a wallet index. Index 0 adds no seed, so wallets created
+    /// before sub-wallets existed keep their address.
+    pub fn index_seed(wallet_index: u16) -> Vec<u8> {
+        if wallet_index == 0 {
+            Vec::new()
+        } else {
+            wallet_index.to_le_bytes().to_vec()
+        }
+    }
+
     pub fn has_active_subscriptions(&self) -> bool {
         self.total_subscriptions > 0
     }
 
+    /// Reserve funds for a new subscription so they can't be withdrawn
+    pub fn commit(&mut self, amount: u64) -> Result<()> {
+        self.committed_amount = self.committed_amount
+            .checked_add(amount)
+            .ok_or(ErrorCodes::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Release funds previously reserved by a subscription
+    pub fn release(&mut self, amount: u64) {
+        self.committed_amount = self.committed_amount.saturating_sub(amount);
+    }
+
+    /// Swap a subscription's reservation for a new amount
+    pub fn recommit(&mut self, old_amount: u64, new_amount: u64) -> Result<()> {
+        self.release(old_amount);
+        self.commit(new_amount)
+    }
+
     pub fn calculate_yield_value(&self, vault_total_value: u64, vault_total_shares: u64) -> u64 {
         if vault_total_shares == 0 || !self.is_yield_enabled {
             return 0;
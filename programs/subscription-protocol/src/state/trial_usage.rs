@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(InitSpace)]
+pub struct TrialUsage {
+    pub user: Pubkey,
+
+    pub merchant_plan: Pubkey,
+
+    pub subscription: Pubkey,
+
+    pub started_at: i64,
+
+    pub bump: u8,
+}
+
+impl TrialUsage {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + 1;
+
+    /// Get the trial usage PDA (one trial per user per plan)
+    pub fn get_pda(user: &Pubkey, merchant_plan: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                b"trial_usage",
+                user.as_ref(),
+                merchant_plan.as_ref(),
+            ],
+            program_id,
+        )
+    }
+
+    pub fn is_used(&self) -> bool {
+        self.started_at != 0
+    }
+}
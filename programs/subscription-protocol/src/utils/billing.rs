@@ -0,0 +1,533 @@
+use anchor_lang::prelude::*;
+use crate::{
+    SubscriptionState, SubscriptionWallet, MerchantPlan, BillingMode, ProtocolConfig,
+    ExpiryReason, PaymentExecuted, PriceChangeActionRequired, TrialConverted,
+    SubscriptionPastDue, SubscriptionReactivated, SubscriptionResumed,
+    SubscriptionExpired, PayeePayout, OracleConversion, ErrorCodes
+};
+use crate::utils::{
+    calculate_subscription_commitment, calculate_payment_split, calculate_prorated_amount,
+    settlement_amount, OracleQuote
+};
+
+/// A due charge resolved against the plan's pricing and missed-period policy
+pub struct DueCharge {
+    pub amount: u64,
+    pub credit_applied: u64,
+    pub discount_amount: u64,
+    pub discounted_periods: u64,
+    pub periods_charged: u64,
+    pub usage_units: u64,
+    pub included_units: u64,
+    pub overage_amount: u64,
+    pub next_anchor: i64,
+    pub converting_trial: bool,
+    pub oracle_conversion: Option<OracleConversion>,
+}
+
+/// Outcome of resolving a due subscription
+pub enum PreparedCharge {
+    /// Charge is ready to be transferred
+    Due(DueCharge),
+    /// Subscriber must accept a new price before being charged
+    PriceActionRequired,
+    /// Charge is above the subscriber's per-charge limit
+    ChargeLimitExceeded,
+    /// Oracle price is further from its EMA than the subscriber accepts
+    PriceSlippageExceeded,
+    /// A subscriber spending limit was reached and the subscription expired
+    Expired,
+}
+
+/// What a number of billing periods costs before credit is applied
+pub struct PeriodPrice {
+    pub gross_amount: u64,
+    pub included_units: u64,
+    pub overage_amount: u64,
+    pub discount_amount: u64,
+    pub discounted_periods: u64,
+}
+
+/// How a charge is divided between the merchant, the treasury and the keeper
+pub struct ChargeSplit {
+    pub protocol_fee: u64,
+    pub merchant_receives: u64,
+    pub treasury_receives: u64,
+    pub keeper: Option<Pubkey>,
+    pub keeper_reward: u64,
+}
+
+/// Resolve what a due subscription owes right now
+/// Applies an accepted price change, the plan's missed-period policy and
+/// the subscriber's spending limits
+/// 
+/// # Arguments
+/// * `subscription` - Subscription being charged (must have billable periods)
+/// * `wallet` - Wallet paying for it (its commitment follows price changes)
+/// * `merchant_plan` - The subscription's plan
+/// * `quote` - Oracle price for oracle-priced plans (converts the charge into tokens)
+/// * `current_time` - Current unix timestamp
+/// 
+/// # Returns
+/// * `PreparedCharge::Due` with the charge, or why nothing should be charged
+pub fn prepare_charge(
+    subscription: &mut Account<SubscriptionState>,
+    wallet: &mut Account<SubscriptionWallet>,
+    merchant_plan: &Account<MerchantPlan>,
+    quote: Option<&OracleQuote>,
+    current_time: i64,
+) -> Result<PreparedCharge> {
+    if let Some(reason) = subscription.limit_reached() {
+        expire_subscription(subscription, reason, current_time);
+        return Ok(PreparedCharge::Expired);
+    }
+
+    if let Some(quote) = quote {
+        if subscription.exceeds_price_slippage(quote.slippage_bps()) {
+            return Ok(PreparedCharge::PriceSlippageExceeded);
+        }
+    }
+
+    let converting_trial = subscription.is_in_trial();
+
+    // Apply the accepted price once it takes effect. The accepted version is
+    // stored on the subscription, so it still applies after the plan has
+    // published a newer one.
+    if subscription.price_version < subscription.accepted_price_version
+        && current_time >= subscription.accepted_effective_at
+    {
+        let new_period_fee = subscription.scaled_fee(subscription.accepted_fee_amount)
+            .ok_or(ErrorCodes::MathOverflow)?;
+        let new_commitment = settlement_amount(
+            quote,
+            calculate_subscription_commitment(new_period_fee)?,
+        )?;
+        wallet.recommit(subscription.committed_amount, new_commitment)?;
+
+        subscription.fee_amount = subscription.accepted_fee_amount;
+        subscription.committed_amount = new_commitment;
+        subscription.price_version = subscription.accepted_price_version;
+        subscription.price_action_required = false;
+    }
+
+    // A version is only published after the previous cutoff, so any version
+    // older than the latest is already past its grandfathering window
+    if subscription.price_version < merchant_plan.price_version {
+        let latest_unaccepted = subscription.accepted_price_version < merchant_plan.price_version;
+        let grandfathering_over = subscription.price_version + 1 < merchant_plan.price_version
+            || current_time >= merchant_plan.price_cutoff_at;
+
+        if latest_unaccepted && grandfathering_over {
+            // Grandfathering has ended and the subscriber never accepted the
+            // new price, so nothing is charged until they act
+            if !subscription.price_action_required {
+                subscription.price_action_required = true;
+
+                emit!(PriceChangeActionRequired {
+                    subscription_pda: subscription.key(),
+                    user: subscription.user,
+                    merchant: subscription.merchant,
+                    price_version: merchant_plan.price_version,
+                    new_fee_amount: merchant_plan.pending_fee_amount,
+                });
+            }
+
+            return Ok(PreparedCharge::PriceActionRequired);
+        }
+    }
+
+    // Anchored billing: settle elapsed periods according to the plan policy
+    let periods_due = subscription.billable_periods(current_time, merchant_plan.billing_mode);
+    let (periods_charged, periods_advanced) = merchant_plan.missed_period_policy.resolve(periods_due);
+    let next_anchor = subscription.anchor_after(periods_advanced)
+        .ok_or(ErrorCodes::MathOverflow)?;
+
+    let usage_units = subscription.period_usage;
+    let price = price_periods(subscription, periods_charged)?;
+
+    // Oracle-priced plans are converted into tokens before credit and limits apply
+    let gross_amount = settlement_amount(quote, price.gross_amount)?;
+    let oracle_conversion = quote.map(|quote| quote.conversion(price.gross_amount));
+
+    // Credit left over from a downgrade pays for what it can
+    let credit_applied = subscription.proration_credit.min(gross_amount);
+    let amount = gross_amount - credit_applied;
+
+    if subscription.exceeds_charge_limit(amount) {
+        return Ok(PreparedCharge::ChargeLimitExceeded);
+    }
+    if subscription.exceeds_total_spend(amount) {
+        expire_subscription(subscription, ExpiryReason::MaxTotalSpend, current_time);
+        return Ok(PreparedCharge::Expired);
+    }
+
+    Ok(PreparedCharge::Due(DueCharge {
+        amount,
+        credit_applied,
+        discount_amount: price.discount_amount,
+        discounted_periods: price.discounted_periods,
+        periods_charged,
+        usage_units,
+        included_units: price.included_units,
+        overage_amount: price.overage_amount,
+        next_anchor,
+        converting_trial,
+        oracle_conversion,
+    }))
+}
+
+/// Price `periods_charged` periods: base fee for every seat, metered overage
+/// for usage reported since the last charge at the subscription's metering
+/// rates, minus any coupon discount
+pub fn price_periods(
+    subscription: &SubscriptionState,
+    periods_charged: u64,
+) -> Result<PeriodPrice> {
+    let period_fee = subscription.period_fee().ok_or(ErrorCodes::MathOverflow)?;
+
+    let (included_units, overage_amount) = subscription.metering
+        .usage_charge(subscription.period_usage, periods_charged)
+        .ok_or(ErrorCodes::MathOverflow)?;
+
+    let (discount_amount, discounted_periods) = match subscription.coupon_discount {
+        Some(discount) => {
+            let periods = periods_charged.min(subscription.coupon_periods_remaining as u64);
+            let amount = discount.discount_for(period_fee)
+                .checked_mul(periods)
+                .ok_or(ErrorCodes::MathOverflow)?;
+            (amount, periods)
+        }
+        None => (0, 0),
+    };
+
+    let gross_amount = period_fee
+        .checked_mul(periods_charged)
+        .and_then(|base| base.checked_add(overage_amount))
+        .and_then(|total| total.checked_sub(discount_amount))
+        .ok_or(ErrorCodes::MathOverflow)?;
+
+    Ok(PeriodPrice {
+        gross_amount,
+        included_units,
+        overage_amount,
+        discount_amount,
+        discounted_periods,
+    })
+}
+
+/// End a pause that has run out, shifting the billing anchor as resume would
+/// Pauses end on their own at `paused_until`; nobody has to call resume first
+pub fn resume_lapsed_pause(
+    subscription: &mut Account<SubscriptionState>,
+    current_time: i64,
+) -> Result<()> {
+    if !subscription.has_pause() || subscription.is_paused(current_time) {
+        return Ok(());
+    }
+
+    let paused_seconds = subscription.end_pause(current_time)?;
+
+    emit!(SubscriptionResumed {
+        subscription_pda: subscription.key(),
+        user: subscription.user,
+        merchant: subscription.merchant,
+        paused_seconds,
+        next_payment_due: subscription.next_payment_due(),
+    });
+
+    Ok(())
+}
+
+/// End a subscription whose spending limit has been reached
+/// The subscriber closes it with cancel_subscription_wallet
+pub fn expire_subscription(
+    subscription: &mut Account<SubscriptionState>,
+    reason: ExpiryReason,
+    current_time: i64,
+) {
+    subscription.is_active = false;
+    subscription.expired_at = current_time;
+
+    emit!(SubscriptionExpired {
+        subscription_pda: subscription.key(),
+        user: subscription.user,
+        merchant: subscription.merchant,
+        reason,
+        total_paid: subscription.total_paid,
+        payment_count: subscription.payment_count,
+    });
+}
+
+/// Split a charge into merchant, treasury and keeper amounts
+/// The keeper reward comes out of the protocol fee, not the merchant's share
+pub fn split_charge(
+    amount: u64,
+    protocol_config: &ProtocolConfig,
+    keeper: Option<Pubkey>,
+) -> Result<ChargeSplit> {
+    let (protocol_fee, merchant_receives) = calculate_payment_split(amount, protocol_config)?;
+
+    let keeper_reward = match keeper {
+        Some(_) => protocol_config.calculate_keeper_reward(amount, protocol_fee),
+        None => 0,
+    };
+    let treasury_receives = protocol_fee
+        .checked_sub(keeper_reward)
+        .ok_or(ErrorCodes::MathOverflow)?;
+
+    Ok(ChargeSplit {
+        protocol_fee,
+        merchant_receives,
+        treasury_receives,
+        keeper,
+        keeper_reward,
+    })
+}
+
+/// Record a transferred charge on the subscription and wallet and emit its events
+pub fn record_charge(
+    subscription: &mut Account<SubscriptionState>,
+    wallet: &mut Account<SubscriptionWallet>,
+    merchant_plan: &Account<MerchantPlan>,
+    charge: &DueCharge,
+    split: &ChargeSplit,
+    payee_payouts: Vec<PayeePayout>,
+    current_time: i64,
+) -> Result<()> {
+    subscription.last_payment_timestamp = charge.next_anchor;
+    subscription.proration_credit = subscription.proration_credit
+        .saturating_sub(charge.credit_applied);
+    subscription.period_usage = subscription.period_usage
+        .saturating_sub(charge.usage_units);
+    let coupon = subscription.coupon;
+    if charge.discounted_periods > 0 {
+        subscription.consume_coupon(charge.discounted_periods);
+    }
+    subscription.total_paid = subscription.total_paid
+        .checked_add(charge.amount)
+        .ok_or(ErrorCodes::MathOverflow)?;
+    subscription.payment_count = subscription.payment_count
+        .checked_add(1)
+        .ok_or(ErrorCodes::MathOverflow)?;
+    subscription.last_charge_amount = charge.amount;
+    subscription.last_charge_refunded = 0;
+
+    wallet.total_spent = wallet.total_spent
+        .checked_add(charge.amount)
+        .ok_or(ErrorCodes::MathOverflow)?;
+
+    emit!(PaymentExecuted {
+        subscription_pda: subscription.key(),
+        wallet_pda: wallet.key(),
+        user: subscription.user,
+        merchant: subscription.merchant,
+        amount: charge.amount,
+        protocol_fee: split.protocol_fee,
+        merchant_received: split.merchant_receives,
+        payment_number: subscription.payment_count,
+        periods_settled: charge.periods_charged as u32,
+        keeper: split.keeper,
+        keeper_reward: split.keeper_reward,
+        usage_units: charge.usage_units,
+        included_units: charge.included_units,
+        overage_amount: charge.overage_amount,
+        discount_amount: charge.discount_amount,
+        coupon: if charge.discounted_periods > 0 { coupon } else { None },
+        payee_payouts,
+        oracle_conversion: charge.oracle_conversion,
+    });
+
+    // A successful charge clears failed attempts and reactivates a suspended
+    // subscription. Only MarkPastDue can leave periods outstanding after settling.
+    let was_suspended = subscription.is_suspended;
+    subscription.reset_dunning(current_time, merchant_plan.billing_mode);
+    if was_suspended {
+        emit!(SubscriptionReactivated {
+            subscription_pda: subscription.key(),
+            user: subscription.user,
+            merchant: subscription.merchant,
+        });
+    }
+    if subscription.is_past_due {
+        emit!(SubscriptionPastDue {
+            subscription_pda: subscription.key(),
+            user: subscription.user,
+            merchant: subscription.merchant,
+            periods_outstanding: subscription.billable_periods(current_time, merchant_plan.billing_mode) as u32,
+        });
+    }
+
+    if charge.converting_trial {
+        emit!(TrialConverted {
+            subscription_pda: subscription.key(),
+            user: subscription.user,
+            merchant: subscription.merchant,
+            plan_pda: merchant_plan.key(),
+            amount: charge.amount,
+        });
+    }
+
+    if let Some(reason) = subscription.limit_reached() {
+        expire_subscription(subscription, reason, current_time);
+    }
+
+    Ok(())
+}
+
+/// Amount owed back to a subscriber whose subscription ends now
+/// Covers the unused time of a period paid in advance plus any downgrade
+/// credit, capped at what hasn't been refunded yet
+pub fn unused_period_amount(
+    subscription: &SubscriptionState,
+    merchant_plan: &MerchantPlan,
+    current_time: i64,
+) -> Result<u64> {
+    let unused = if merchant_plan.billing_mode == BillingMode::InAdvance
+        && !subscription.is_in_trial()
+        && subscription.payment_count > 0
+    {
+        calculate_prorated_amount(
+            subscription.period_fee().ok_or(ErrorCodes::MathOverflow)?,
+            subscription.payment_interval,
+            subscription.next_payment_due() - current_time,
+        )?
+    } else {
+        0
+    };
+
+    Ok(unused
+        .checked_add(subscription.proration_credit)
+        .ok_or(ErrorCodes::MathOverflow)?
+        .min(subscription.refundable_amount()))
+}
+
+/// Prorate a mid-period price change for the rest of the current period
+/// In advance, the old price already paid for the period, so only the
+/// remaining portion is repriced. In arrears, the next charge bills the new
+/// price for the whole period, so the elapsed portion is settled now.
+/// 
+/// # Arguments
+/// * `subscription` - Subscription before the change
+/// * `billing_mode` - The plan's billing mode
+/// * `old_period_fee` - Amount billed per period before the change
+/// * `new_period_fee` - Amount billed per period after the change
+/// * `new_interval` - Period length after the change
+/// * `current_time` - Current unix timestamp
+/// 
+/// # Returns
+/// * `(charge, credit)` - at most one is non-zero; trials prorate nothing
+pub fn calculate_period_proration(
+    subscription: &SubscriptionState,
+    billing_mode: BillingMode,
+    old_period_fee: u64,
+    new_period_fee: u64,
+    new_interval: i64,
+    current_time: i64,
+) -> Result<(u64, u64)> {
+    if subscription.is_in_trial() {
+        return Ok((0, 0));
+    }
+
+    let old_interval = subscription.payment_interval;
+    let remaining = subscription.next_payment_due() - current_time;
+    let elapsed = old_interval - remaining;
+
+    let old_remaining = calculate_prorated_amount(old_period_fee, old_interval, remaining)?;
+    let new_remaining = calculate_prorated_amount(new_period_fee, new_interval, remaining)?;
+
+    let (owed, paid) = match billing_mode {
+        BillingMode::InAdvance => (new_remaining, old_remaining),
+        BillingMode::InArrears => {
+            let old_elapsed = calculate_prorated_amount(old_period_fee, old_interval, elapsed)?;
+            let owed = old_elapsed
+                .checked_add(new_remaining)
+                .ok_or(ErrorCodes::MathOverflow)?;
+            (owed, new_period_fee)
+        }
+    };
+
+    Ok((owed.saturating_sub(paid), paid.saturating_sub(owed)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CouponDiscount, MeteringConfig};
+
+    #[test]
+    fn price_periods_bills_every_seat() {
+        let mut subscription = SubscriptionState::for_test(100, 30);
+        subscription.quantity = 3;
+
+        let price = price_periods(&subscription, 2).unwrap();
+        assert_eq!(price.gross_amount, 600);
+        assert_eq!(price.overage_amount, 0);
+        assert_eq!(price.discount_amount, 0);
+    }
+
+    #[test]
+    fn price_periods_adds_overage_at_subscription_rates() {
+        let mut subscription = SubscriptionState::for_test(100, 30);
+        subscription.metering = MeteringConfig { unit_price: 2, included_units: 10, max_units: 100 };
+        subscription.period_usage = 25;
+
+        let price = price_periods(&subscription, 1).unwrap();
+        assert_eq!(price.included_units, 10);
+        assert_eq!(price.overage_amount, 30);
+        assert_eq!(price.gross_amount, 130);
+    }
+
+    #[test]
+    fn price_periods_discounts_remaining_coupon_periods_only() {
+        let mut subscription = SubscriptionState::for_test(100, 30);
+        subscription.apply_coupon(Pubkey::new_unique(), CouponDiscount::PercentOff { bps: 5_000 }, 1);
+
+        let price = price_periods(&subscription, 3).unwrap();
+        assert_eq!(price.discounted_periods, 1);
+        assert_eq!(price.discount_amount, 50);
+        assert_eq!(price.gross_amount, 250);
+    }
+
+    #[test]
+    fn price_periods_reports_overflow() {
+        let subscription = SubscriptionState::for_test(u64::MAX, 30);
+        assert!(price_periods(&subscription, 2).is_err());
+    }
+
+    // Period runs from 0 to 100; a quarter of it has elapsed
+    const NOW: i64 = 25;
+
+    #[test]
+    fn in_advance_proration_reprices_the_rest_of_the_period() {
+        let subscription = SubscriptionState::for_test(100, 100);
+
+        let upgrade = calculate_period_proration(&subscription, BillingMode::InAdvance, 100, 200, 100, NOW);
+        assert_eq!(upgrade.unwrap(), (75, 0));
+
+        let downgrade = calculate_period_proration(&subscription, BillingMode::InAdvance, 200, 100, 100, NOW);
+        assert_eq!(downgrade.unwrap(), (0, 75));
+    }
+
+    #[test]
+    fn in_arrears_proration_settles_against_the_next_full_charge() {
+        let subscription = SubscriptionState::for_test(100, 100);
+
+        // Owes 25 at the old price and 150 at the new one; the next charge bills 200
+        let upgrade = calculate_period_proration(&subscription, BillingMode::InArrears, 100, 200, 100, NOW);
+        assert_eq!(upgrade.unwrap(), (0, 25));
+
+        // Owes 50 at the old price and 75 at the new one; the next charge bills 100
+        let downgrade = calculate_period_proration(&subscription, BillingMode::InArrears, 200, 100, 100, NOW);
+        assert_eq!(downgrade.unwrap(), (25, 0));
+    }
+
+    #[test]
+    fn trial_proration_is_free() {
+        let mut subscription = SubscriptionState::for_test(100, 100);
+        subscription.payment_count = 0;
+        subscription.trial_ends_at = 100;
+
+        let proration = calculate_period_proration(&subscription, BillingMode::InAdvance, 100, 200, 100, NOW);
+        assert_eq!(proration.unwrap(), (0, 0));
+    }
+}
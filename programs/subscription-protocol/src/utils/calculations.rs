@@ -171,40 +171,92 @@ pub fn calculate_current_exchange_rate(
     Ok(rate)
 }
 
+/// Number of payment periods reserved in the wallet for every active subscription
+pub const COMMITTED_PAYMENT_PERIODS: u64 = 3;
+
+/// Calculate the amount a single subscription reserves in its wallet
+/// 
+/// # Arguments
+/// * `fee_amount` - Fee charged per payment period
+/// 
+/// # Returns
+/// * Amount to commit (fee * COMMITTED_PAYMENT_PERIODS)
+/// 
+/// # Example
+/// ```
+/// use subscription_protocol::utils::calculate_subscription_commitment;
+///
+/// let commitment = calculate_subscription_commitment(10_000_000).unwrap();
+/// assert_eq!(commitment, 30_000_000);
+/// ```
+pub fn calculate_subscription_commitment(fee_amount: u64) -> Result<u64> {
+    let commitment = fee_amount
+        .checked_mul(COMMITTED_PAYMENT_PERIODS)
+        .ok_or(ErrorCodes::MathOverflow)?;
+
+    Ok(commitment)
+}
+
 /// Calculate committed balance for a wallet (3-month subscription buffer)
 /// This represents funds that should not be withdrawn
 /// 
+/// The wallet keeps a running total that `subscribe_with_wallet` increases
+/// and `cancel_subscription_wallet` decreases, so no subscription accounts
+/// need to be loaded here.
+/// 
 /// # Arguments
 /// * `wallet` - The subscription wallet
 /// 
 /// # Returns
 /// * Committed amount in lamports
 /// 
-/// # TODO
-/// This is a placeholder. In production, you would:
-/// 1. Query all active subscriptions for this wallet
-/// 2. Calculate 3 months worth of subscription fees
-/// 3. Return the sum
-/// 
 /// # Example
 /// ```
 /// let committed = calculate_committed_balance(&wallet)?;
 /// let withdrawable = wallet_balance - committed;
 /// ```
-pub fn calculate_committed_balance(_wallet: &SubscriptionWallet) -> Result<u64> {
-    // TODO: Query all active subscriptions and calculate 3-month buffer
-    // For now, returns 0 (no restrictions)
-    // 
-    // Production implementation would:
-    // 1. Iterate through active subscriptions
-    // 2. For each subscription: fee_amount * 3
-    // 3. Sum all commitments
-    // 
-    // Example:
-    // if wallet has subscriptions:
-    //   - Netflix: $10/month → $30 committed
-    //   - Spotify: $5/month → $15 committed
-    //   - Total: $45 committed
-    
-    Ok(0)
+pub fn calculate_committed_balance(wallet: &SubscriptionWallet) -> Result<u64> {
+    if !wallet.has_active_subscriptions() {
+        return Ok(0);
+    }
+
+    Ok(wallet.committed_amount)
+}
+
+/// Calculate the cost of part of a billing period
+/// Uses the plan's rate: cost = (fee * seconds) / interval
+/// 
+/// # Arguments
+/// * `fee_amount` - Fee charged per full period
+/// * `payment_interval` - Period length in seconds
+/// * `seconds` - Portion of the period being priced
+/// 
+/// # Returns
+/// * Prorated cost (rounded down)
+/// 
+/// # Example
+/// ```
+/// use subscription_protocol::utils::calculate_prorated_amount;
+///
+/// // Half of a 30-day period at 3,000 per period
+/// let cost = calculate_prorated_amount(3_000, 2_592_000, 1_296_000).unwrap();
+/// assert_eq!(cost, 1_500);
+/// ```
+pub fn calculate_prorated_amount(
+    fee_amount: u64,
+    payment_interval: i64,
+    seconds: i64,
+) -> Result<u64> {
+    if payment_interval <= 0 || seconds <= 0 {
+        return Ok(0);
+    }
+
+    let seconds = seconds.min(payment_interval);
+    let cost = (fee_amount as u128)
+        .checked_mul(seconds as u128)
+        .ok_or(ErrorCodes::MathOverflow)?
+        .checked_div(payment_interval as u128)
+        .ok_or(ErrorCodes::MathOverflow)? as u64;
+
+    Ok(cost)
 }
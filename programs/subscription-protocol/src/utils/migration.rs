@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+
+/// Grow a program account to `new_len` bytes, with `payer` covering the extra rent
+/// Used by migrations that bring accounts created under an older layout up to date
+pub fn grow_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    new_len: usize,
+) -> Result<()> {
+    let rent_shortfall = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(account.lamports());
+    if rent_shortfall > 0 {
+        let cpi_accounts = system_program::Transfer {
+            from: payer.clone(),
+            to: account.clone(),
+        };
+        system_program::transfer(
+            CpiContext::new(system_program.clone(), cpi_accounts),
+            rent_shortfall,
+        )?;
+    }
+
+    account.resize(new_len)?;
+    Ok(())
+}
@@ -1,5 +1,15 @@
 pub mod calculations;
 pub mod vault_operations;
+pub mod payment_operations;
+pub mod billing;
+pub mod token_extensions;
+pub mod oracle;
+pub mod migration;
 
 pub use calculations::*;
-pub use vault_operations::*;
\ No newline at end of file
+pub use vault_operations::*;
+pub use payment_operations::*;
+pub use billing::*;
+pub use token_extensions::*;
+pub use oracle::*;
+pub use migration::*;
@@ -0,0 +1,365 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+use crate::{MerchantPlan, OraclePricing, OracleConversion, ErrorCodes};
+
+/// Pyth Solana receiver program, owner of PriceUpdateV2 accounts
+pub const PYTH_RECEIVER_PROGRAM_ID: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+
+/// Decimals of reference-currency amounts on oracle-priced plans ($9.99 = 9_990_000)
+pub const REFERENCE_DECIMALS: i32 = 6;
+
+/// Anchor discriminator of Pyth's PriceUpdateV2 account
+const PRICE_UPDATE_V2_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+/// Borsh variant index of `VerificationLevel::Full`
+const VERIFICATION_LEVEL_FULL: u8 = 1;
+
+/// A validated oracle price for converting reference amounts into a settlement mint
+#[derive(Clone, Copy, Debug)]
+pub struct OracleQuote {
+    pub price_feed: Pubkey,
+    pub price: i64,
+    pub confidence: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+    pub ema_price: i64,
+    pub mint_decimals: u8,
+}
+
+impl OracleQuote {
+    /// Tokens worth `reference_amount`, rounded up so the merchant is never short
+    pub fn token_amount(&self, reference_amount: u64) -> Option<u64> {
+        let scale = self.mint_decimals as i32 - REFERENCE_DECIMALS - self.exponent;
+        let (numerator, denominator) = if scale >= 0 {
+            (
+                (reference_amount as u128).checked_mul(10u128.checked_pow(scale as u32)?)?,
+                self.price as u128,
+            )
+        } else {
+            (
+                reference_amount as u128,
+                (self.price as u128).checked_mul(10u128.checked_pow(scale.unsigned_abs())?)?,
+            )
+        };
+
+        let amount = numerator
+            .checked_add(denominator - 1)?
+            .checked_div(denominator)?;
+        u64::try_from(amount).ok()
+    }
+
+    /// Gap between the spot and EMA price, in basis points of the EMA price
+    pub fn slippage_bps(&self) -> u64 {
+        if self.ema_price <= 0 {
+            return u64::MAX;
+        }
+
+        let gap = (self.price as i128 - self.ema_price as i128).unsigned_abs();
+        u64::try_from(gap * 10_000 / self.ema_price as u128).unwrap_or(u64::MAX)
+    }
+
+    pub fn conversion(&self, reference_amount: u64) -> OracleConversion {
+        OracleConversion {
+            reference_amount,
+            price_feed: self.price_feed,
+            price: self.price,
+            confidence: self.confidence,
+            exponent: self.exponent,
+            publish_time: self.publish_time,
+        }
+    }
+}
+
+/// Read and validate a Pyth PriceUpdateV2 account against the plan's oracle settings
+///
+/// # Arguments
+/// * `price_feed` - The price account passed to the instruction
+/// * `pricing` - The plan's oracle pricing settings
+/// * `mint` - The settlement mint (selects the feed, and for its decimals)
+/// * `current_time` - Current unix timestamp
+///
+/// # Returns
+/// * The quote, once the feed, staleness and confidence checks pass
+pub fn load_oracle_quote(
+    price_feed: &AccountInfo,
+    pricing: &OraclePricing,
+    mint: &InterfaceAccount<Mint>,
+    current_time: i64,
+) -> Result<OracleQuote> {
+    let expected_feed = pricing
+        .feed_for(&mint.key())
+        .ok_or(ErrorCodes::SettlementMintNotSupported)?;
+    require!(
+        price_feed.key() == expected_feed && *price_feed.owner == PYTH_RECEIVER_PROGRAM_ID,
+        ErrorCodes::InvalidPriceFeed
+    );
+
+    let data = price_feed.try_borrow_data()?;
+    require!(
+        data.len() >= 8 && data[..8] == PRICE_UPDATE_V2_DISCRIMINATOR,
+        ErrorCodes::InvalidPriceFeed
+    );
+
+    // write_authority, verification_level, then the price feed message
+    let mut cursor: &[u8] = &data[8..];
+    let _write_authority = Pubkey::deserialize(&mut cursor)?;
+    let verification_level = u8::deserialize(&mut cursor)?;
+    require!(
+        verification_level == VERIFICATION_LEVEL_FULL,
+        ErrorCodes::InvalidPriceFeed
+    );
+
+    let _feed_id = <[u8; 32]>::deserialize(&mut cursor)?;
+    let price = i64::deserialize(&mut cursor)?;
+    let confidence = u64::deserialize(&mut cursor)?;
+    let exponent = i32::deserialize(&mut cursor)?;
+    let publish_time = i64::deserialize(&mut cursor)?;
+    let _prev_publish_time = i64::deserialize(&mut cursor)?;
+    let ema_price = i64::deserialize(&mut cursor)?;
+
+    require!(price > 0, ErrorCodes::InvalidOraclePrice);
+    require!(
+        current_time.saturating_sub(publish_time) <= pricing.max_staleness_seconds,
+        ErrorCodes::StaleOraclePrice
+    );
+    require!(
+        confidence as u128 * 10_000 <= price as u128 * pricing.max_confidence_bps as u128,
+        ErrorCodes::OracleConfidenceTooWide
+    );
+
+    Ok(OracleQuote {
+        price_feed: price_feed.key(),
+        price,
+        confidence,
+        exponent,
+        publish_time,
+        ema_price,
+        mint_decimals: mint.decimals,
+    })
+}
+
+/// Quote for an oracle-priced plan; `None` for plans priced in tokens
+pub fn quote_for_plan(
+    merchant_plan: &MerchantPlan,
+    price_feed: Option<&AccountInfo>,
+    mint: Option<&InterfaceAccount<Mint>>,
+    current_time: i64,
+) -> Result<Option<OracleQuote>> {
+    let Some(pricing) = merchant_plan.oracle_pricing.as_ref() else {
+        return Ok(None);
+    };
+
+    let (price_feed, mint) = price_feed.zip(mint).ok_or(ErrorCodes::PriceFeedRequired)?;
+    load_oracle_quote(price_feed, pricing, mint, current_time).map(Some)
+}
+
+/// Convert a plan-priced amount into tokens of the settlement mint
+pub fn settlement_amount(quote: Option<&OracleQuote>, amount: u64) -> Result<u64> {
+    match quote {
+        Some(quote) => quote.token_amount(amount).ok_or(ErrorCodes::MathOverflow.into()),
+        None => Ok(amount),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::program_pack::Pack;
+    use anchor_spl::token_2022::spl_token_2022;
+    use crate::MintPriceFeed;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn quote(price: i64, exponent: i32, mint_decimals: u8) -> OracleQuote {
+        OracleQuote {
+            price_feed: Pubkey::default(),
+            price,
+            confidence: 0,
+            exponent,
+            publish_time: NOW,
+            ema_price: price,
+            mint_decimals,
+        }
+    }
+
+    #[test]
+    fn token_amount_converts_reference_amount() {
+        // $9.99 of SOL at $150.00 (8-decimal feed, 9-decimal mint)
+        assert_eq!(quote(150_00000000, -8, 9).token_amount(9_990_000), Some(66_600_000));
+        // $9.99 of a $1.00 stablecoin with 6 decimals
+        assert_eq!(quote(1_00000000, -8, 6).token_amount(9_990_000), Some(9_990_000));
+    }
+
+    #[test]
+    fn token_amount_rounds_up() {
+        assert_eq!(quote(3, 0, 6).token_amount(10), Some(4));
+        // Negative scale divides the price side instead
+        assert_eq!(quote(2, 0, 0).token_amount(9_990_000), Some(5));
+    }
+
+    #[test]
+    fn token_amount_overflow_is_none() {
+        assert_eq!(quote(1, -8, 9).token_amount(u64::MAX), None);
+    }
+
+    #[test]
+    fn slippage_bps_measures_gap_to_ema() {
+        let mut quote = quote(101, 0, 6);
+        quote.ema_price = 100;
+        assert_eq!(quote.slippage_bps(), 100);
+
+        quote.price = 99;
+        assert_eq!(quote.slippage_bps(), 100);
+
+        quote.ema_price = 0;
+        assert_eq!(quote.slippage_bps(), u64::MAX);
+    }
+
+    struct PriceUpdate {
+        verification_level: u8,
+        price: i64,
+        confidence: u64,
+        publish_time: i64,
+    }
+
+    impl PriceUpdate {
+        fn new(price: i64, confidence: u64, publish_time: i64) -> Self {
+            Self { verification_level: VERIFICATION_LEVEL_FULL, price, confidence, publish_time }
+        }
+
+        fn data(&self) -> Vec<u8> {
+            let mut data = PRICE_UPDATE_V2_DISCRIMINATOR.to_vec();
+            data.extend_from_slice(Pubkey::new_unique().as_ref());
+            data.push(self.verification_level);
+            data.extend_from_slice(&[7u8; 32]);
+            data.extend_from_slice(&self.price.to_le_bytes());
+            data.extend_from_slice(&self.confidence.to_le_bytes());
+            data.extend_from_slice(&(-8i32).to_le_bytes());
+            data.extend_from_slice(&self.publish_time.to_le_bytes());
+            data.extend_from_slice(&(self.publish_time - 1).to_le_bytes());
+            data.extend_from_slice(&(self.price - 1_000_000).to_le_bytes());
+            data.extend_from_slice(&self.confidence.to_le_bytes());
+            data.extend_from_slice(&0u64.to_le_bytes());
+            data
+        }
+    }
+
+    fn mint_data(decimals: u8) -> Vec<u8> {
+        let mint = spl_token_2022::state::Mint {
+            decimals,
+            is_initialized: true,
+            ..Default::default()
+        };
+        let mut data = vec![0u8; spl_token_2022::state::Mint::LEN];
+        spl_token_2022::state::Mint::pack(mint, &mut data).unwrap();
+        data
+    }
+
+    fn pricing(feeds: &[(Pubkey, Pubkey)]) -> OraclePricing {
+        OraclePricing {
+            price_feeds: feeds
+                .iter()
+                .map(|&(mint, price_feed)| MintPriceFeed { mint, price_feed })
+                .collect(),
+            max_staleness_seconds: 60,
+            max_confidence_bps: 100,
+        }
+    }
+
+    /// Run `load_oracle_quote` against locally built feed and mint accounts
+    fn load(
+        update: &PriceUpdate,
+        feed_key: Pubkey,
+        feed_owner: Pubkey,
+        mint_key: Pubkey,
+        pricing: &OraclePricing,
+    ) -> Result<OracleQuote> {
+        let (mut feed_lamports, mut feed_data) = (1u64, update.data());
+        let feed_info = AccountInfo::new(
+            &feed_key, false, false, &mut feed_lamports, &mut feed_data, &feed_owner, false, 0,
+        );
+
+        let token_program = spl_token_2022::ID;
+        let (mut mint_lamports, mut mint_data) = (1u64, mint_data(9));
+        let mint_info = AccountInfo::new(
+            &mint_key, false, false, &mut mint_lamports, &mut mint_data, &token_program, false, 0,
+        );
+        let mint = InterfaceAccount::<Mint>::try_from(&mint_info)?;
+
+        load_oracle_quote(&feed_info, pricing, &mint, NOW)
+    }
+
+    #[test]
+    fn load_oracle_quote_parses_price_update() {
+        let (mint, feed) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let update = PriceUpdate::new(150_00000000, 5_000_000, NOW - 10);
+
+        let quote = load(&update, feed, PYTH_RECEIVER_PROGRAM_ID, mint, &pricing(&[(mint, feed)])).unwrap();
+        assert_eq!(quote.price_feed, feed);
+        assert_eq!(quote.price, 150_00000000);
+        assert_eq!(quote.confidence, 5_000_000);
+        assert_eq!(quote.exponent, -8);
+        assert_eq!(quote.publish_time, NOW - 10);
+        assert_eq!(quote.ema_price, 150_00000000 - 1_000_000);
+        assert_eq!(quote.mint_decimals, 9);
+    }
+
+    #[test]
+    fn load_oracle_quote_uses_the_settlement_mint_feed() {
+        let (plan_mint, plan_feed) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (other_mint, other_feed) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pricing = pricing(&[(plan_mint, plan_feed), (other_mint, other_feed)]);
+        let update = PriceUpdate::new(150_00000000, 0, NOW);
+
+        assert!(load(&update, other_feed, PYTH_RECEIVER_PROGRAM_ID, other_mint, &pricing).is_ok());
+        assert_eq!(
+            load(&update, plan_feed, PYTH_RECEIVER_PROGRAM_ID, other_mint, &pricing).unwrap_err(),
+            ErrorCodes::InvalidPriceFeed.into()
+        );
+        assert_eq!(
+            load(&update, plan_feed, PYTH_RECEIVER_PROGRAM_ID, Pubkey::new_unique(), &pricing).unwrap_err(),
+            ErrorCodes::SettlementMintNotSupported.into()
+        );
+    }
+
+    #[test]
+    fn load_oracle_quote_rejects_unverified_or_foreign_accounts() {
+        let (mint, feed) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pricing = pricing(&[(mint, feed)]);
+
+        let update = PriceUpdate::new(150_00000000, 0, NOW);
+        assert_eq!(
+            load(&update, feed, Pubkey::new_unique(), mint, &pricing).unwrap_err(),
+            ErrorCodes::InvalidPriceFeed.into()
+        );
+
+        let partial = PriceUpdate { verification_level: 0, ..update };
+        assert_eq!(
+            load(&partial, feed, PYTH_RECEIVER_PROGRAM_ID, mint, &pricing).unwrap_err(),
+            ErrorCodes::InvalidPriceFeed.into()
+        );
+    }
+
+    #[test]
+    fn load_oracle_quote_checks_price_staleness_and_confidence() {
+        let (mint, feed) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pricing = pricing(&[(mint, feed)]);
+        let load = |update: PriceUpdate| load(&update, feed, PYTH_RECEIVER_PROGRAM_ID, mint, &pricing);
+
+        assert_eq!(
+            load(PriceUpdate::new(0, 0, NOW)).unwrap_err(),
+            ErrorCodes::InvalidOraclePrice.into()
+        );
+        assert_eq!(
+            load(PriceUpdate::new(150_00000000, 0, NOW - 61)).unwrap_err(),
+            ErrorCodes::StaleOraclePrice.into()
+        );
+        assert!(load(PriceUpdate::new(150_00000000, 0, NOW - 60)).is_ok());
+        // 100 bps of $150 is $1.50
+        assert!(load(PriceUpdate::new(150_00000000, 1_50000000, NOW)).is_ok());
+        assert_eq!(
+            load(PriceUpdate::new(150_00000000, 1_50000001, NOW)).unwrap_err(),
+            ErrorCodes::OracleConfidenceTooWide.into()
+        );
+    }
+}
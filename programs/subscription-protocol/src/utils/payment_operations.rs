@@ -0,0 +1,468 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::system_program;
+use anchor_spl::token_interface::{
+    self, Mint, TokenInterface, TokenAccount, TransferChecked, CloseAccount
+};
+use crate::{SubscriptionWallet, MerchantPlan, ProtocolConfig, RefundPolicy, PayeePayout, ErrorCodes};
+use crate::utils::{amount_with_transfer_fee, is_native_mint};
+
+/// Split a charge into the protocol fee and the merchant's share
+/// 
+/// # Arguments
+/// * `amount` - Total amount charged to the subscriber
+/// * `protocol_config` - Protocol configuration holding the fee rate
+/// 
+/// # Returns
+/// * `(protocol_fee, merchant_receives)`
+pub fn calculate_payment_split(
+    amount: u64,
+    protocol_config: &ProtocolConfig,
+) -> Result<(u64, u64)> {
+    let protocol_fee = protocol_config.calculate_fee(amount);
+    let merchant_receives = amount
+        .checked_sub(protocol_fee)
+        .ok_or(ErrorCodes::MathOverflow)?;
+
+    Ok((protocol_fee, merchant_receives))
+}
+
+/// Work out what a subscriber gets back for a refunded amount
+/// 
+/// # Arguments
+/// * `amount` - Portion of a past charge being refunded
+/// * `protocol_config` - Protocol configuration holding the fee rate
+/// * `refund_policy` - The plan's refund policy
+/// 
+/// # Returns
+/// * `(merchant_refund, protocol_fee_reversed)` - paid back by the merchant and
+///   by the treasury respectively; the fee is only reversed when the policy says so
+pub fn calculate_refund(
+    amount: u64,
+    protocol_config: &ProtocolConfig,
+    refund_policy: &RefundPolicy,
+) -> Result<(u64, u64)> {
+    let (protocol_fee, merchant_share) = calculate_payment_split(amount, protocol_config)?;
+
+    if refund_policy.reverse_protocol_fee {
+        Ok((merchant_share, protocol_fee))
+    } else {
+        Ok((merchant_share, 0))
+    }
+}
+
+/// Pay the protocol fee share of a refund back out of the treasury
+/// The config authority signs, so the treasury token account must be owned by
+/// it or have approved it as delegate
+/// 
+/// # Arguments
+/// * `protocol_treasury` - Treasury token account that collected the fee
+/// * `protocol_authority` - Protocol config authority
+/// * `destination` - Subscriber's wallet token account
+/// * `mint` - The subscription's mint
+/// * `token_program` - SPL Token or Token-2022 program
+/// * `amount` - Fee being reversed (no-op when zero)
+pub fn reverse_protocol_fee<'info>(
+    protocol_treasury: Option<&InterfaceAccount<'info, TokenAccount>>,
+    protocol_authority: Option<&Signer<'info>>,
+    destination: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let protocol_treasury = protocol_treasury.ok_or(ErrorCodes::FeeReversalAccountsRequired)?;
+    let protocol_authority = protocol_authority.ok_or(ErrorCodes::FeeReversalAccountsRequired)?;
+
+    let cpi_accounts = TransferChecked {
+        from: protocol_treasury.to_account_info(),
+        mint: mint.to_account_info(),
+        to: destination.to_account_info(),
+        authority: protocol_authority.to_account_info(),
+    };
+    token_interface::transfer_checked(
+        CpiContext::new(token_program.to_account_info(), cpi_accounts),
+        amount,
+        mint.decimals,
+    )
+}
+
+/// Transfer tokens out of a subscription wallet
+/// Uses the wallet PDA as signer. The wallet covers any Token-2022 transfer
+/// fee, so `destination` receives exactly `amount`.
+/// 
+/// # Arguments
+/// * `wallet` - The subscription wallet (token account authority)
+/// * `wallet_token_account` - Source token account
+/// * `destination` - Destination token account
+/// * `mint` - The wallet's mint
+/// * `token_program` - SPL Token or Token-2022 program
+/// * `amount` - Amount the destination receives (no-op when zero)
+pub fn transfer_from_wallet<'info>(
+    wallet: &Account<'info, SubscriptionWallet>,
+    wallet_token_account: &InterfaceAccount<'info, TokenAccount>,
+    destination: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<()> {
+    transfer_from_wallet_to(
+        wallet,
+        wallet_token_account,
+        destination.to_account_info(),
+        mint,
+        token_program,
+        amount,
+    )
+}
+
+/// Transfer tokens out of a subscription wallet to an unchecked token account
+/// Callers must validate `destination` themselves
+fn transfer_from_wallet_to<'info>(
+    wallet: &Account<'info, SubscriptionWallet>,
+    wallet_token_account: &InterfaceAccount<'info, TokenAccount>,
+    destination: AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let owner_key = wallet.owner;
+    let mint_key = wallet.mint;
+    let index_seed = SubscriptionWallet::index_seed(wallet.wallet_index);
+    let bump = wallet.bump;
+    let seeds = &[
+        b"subscription_wallet",
+        owner_key.as_ref(),
+        mint_key.as_ref(),
+        index_seed.as_slice(),
+        &[bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = TransferChecked {
+        from: wallet_token_account.to_account_info(),
+        mint: mint.to_account_info(),
+        to: destination,
+        authority: wallet.to_account_info(),
+    };
+
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        cpi_accounts,
+        signer_seeds,
+    );
+
+    token_interface::transfer_checked(
+        cpi_ctx,
+        amount_with_transfer_fee(mint, amount)?,
+        mint.decimals,
+    )
+}
+
+/// Wallet balance needed to pay a charge, including the transfer fee on every leg
+/// 
+/// # Arguments
+/// * `merchant_plan` - Plan whose revenue splits apply
+/// * `mint` - The plan's mint
+/// * `merchant_receives` - Merchant's share (one leg per payee on split plans)
+/// * `other_transfers` - Remaining legs, such as the treasury and keeper shares
+pub fn payment_cost(
+    merchant_plan: &MerchantPlan,
+    mint: &InterfaceAccount<Mint>,
+    merchant_receives: u64,
+    other_transfers: &[u64],
+) -> Result<u64> {
+    let merchant_legs = if merchant_plan.has_revenue_splits() {
+        merchant_plan.split_shares(merchant_receives)
+    } else {
+        vec![merchant_receives]
+    };
+
+    merchant_legs
+        .iter()
+        .chain(other_transfers)
+        .try_fold(0u64, |total, &leg| {
+            total
+                .checked_add(amount_with_transfer_fee(mint, leg)?)
+                .ok_or(ErrorCodes::MathOverflow.into())
+        })
+}
+
+/// Token accounts a charge is paid out of and into
+pub struct PaymentAccounts<'a, 'info> {
+    /// Source token account, owned by the subscription wallet
+    pub wallet_token_account: &'a InterfaceAccount<'info, TokenAccount>,
+    /// Merchant destination (unused when the plan splits revenue)
+    pub merchant_token_account: &'a InterfaceAccount<'info, TokenAccount>,
+    /// Protocol fee destination
+    pub protocol_treasury: &'a InterfaceAccount<'info, TokenAccount>,
+    /// One token account per revenue split, in split order
+    pub payee_token_accounts: &'a [AccountInfo<'info>],
+    /// The plan's mint
+    pub mint: &'a InterfaceAccount<'info, Mint>,
+    /// SPL Token or Token-2022 program
+    pub token_program: &'a Interface<'info, TokenInterface>,
+}
+
+/// Pay a charge out of a subscription wallet to the merchant and protocol treasury
+/// 
+/// # Arguments
+/// * `wallet` - The subscription wallet (token account authority)
+/// * `accounts` - Source, destination and program accounts for the payout
+/// * `merchant_plan` - Plan whose revenue splits apply
+/// * `merchant_receives` - Amount sent to the merchant or shared across payees
+/// * `protocol_fee` - Amount sent to the treasury
+/// 
+/// # Returns
+/// * Per-payee breakdown of `merchant_receives` (empty without revenue splits)
+pub fn transfer_payment<'info>(
+    wallet: &Account<'info, SubscriptionWallet>,
+    accounts: &PaymentAccounts<'_, 'info>,
+    merchant_plan: &MerchantPlan,
+    merchant_receives: u64,
+    protocol_fee: u64,
+) -> Result<Vec<PayeePayout>> {
+    let PaymentAccounts {
+        wallet_token_account,
+        merchant_token_account,
+        protocol_treasury,
+        payee_token_accounts,
+        mint,
+        token_program,
+    } = *accounts;
+
+    let payouts = if merchant_plan.has_revenue_splits() {
+        transfer_revenue_splits(
+            wallet,
+            wallet_token_account,
+            payee_token_accounts,
+            merchant_plan,
+            mint,
+            token_program,
+            merchant_receives,
+        )?
+    } else {
+        // Transfer to merchant
+        transfer_from_wallet(
+            wallet,
+            wallet_token_account,
+            merchant_token_account,
+            mint,
+            token_program,
+            merchant_receives,
+        )?;
+        Vec::new()
+    };
+
+    // Transfer protocol fee
+    transfer_from_wallet(
+        wallet,
+        wallet_token_account,
+        protocol_treasury,
+        mint,
+        token_program,
+        protocol_fee,
+    )?;
+
+    Ok(payouts)
+}
+
+/// Share the merchant's proceeds across the plan's revenue split payees
+/// Each payee token account must be owned by its payee and hold the plan's mint
+fn transfer_revenue_splits<'info>(
+    wallet: &Account<'info, SubscriptionWallet>,
+    wallet_token_account: &InterfaceAccount<'info, TokenAccount>,
+    payee_token_accounts: &[AccountInfo<'info>],
+    merchant_plan: &MerchantPlan,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<Vec<PayeePayout>> {
+    require!(
+        payee_token_accounts.len() == merchant_plan.revenue_splits.len(),
+        ErrorCodes::InvalidPayeeAccounts
+    );
+
+    let shares = merchant_plan.split_shares(amount);
+    let mut payouts = Vec::with_capacity(shares.len());
+
+    for ((split, share), payee_info) in merchant_plan.revenue_splits
+        .iter()
+        .zip(shares)
+        .zip(payee_token_accounts)
+    {
+        require!(payee_info.owner == &token_program.key(), ErrorCodes::InvalidPayeeAccounts);
+        let payee_account = TokenAccount::try_deserialize(&mut &payee_info.try_borrow_data()?[..])?;
+        require!(
+            payee_account.owner == split.payee && payee_account.mint == mint.key(),
+            ErrorCodes::InvalidPayeeAccounts
+        );
+
+        transfer_from_wallet_to(
+            wallet,
+            wallet_token_account,
+            payee_info.clone(),
+            mint,
+            token_program,
+            share,
+        )?;
+
+        payouts.push(PayeePayout {
+            payee: split.payee,
+            token_account: payee_info.key(),
+            amount: share,
+        });
+    }
+
+    Ok(payouts)
+}
+
+/// Token account that receives the merchant's share of a charge
+/// Plans that unwrap SOL collect it in the merchant's temporary wSOL account
+/// when the charge settles in native SOL
+pub fn merchant_payout_account<'a, 'info>(
+    merchant_plan: &MerchantPlan,
+    merchant_token_account: &'a InterfaceAccount<'info, TokenAccount>,
+    sol_unwrap_account: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+) -> Result<&'a InterfaceAccount<'info, TokenAccount>> {
+    if merchant_plan.unwrap_sol && is_native_mint(&merchant_token_account.mint) {
+        sol_unwrap_account.ok_or_else(|| ErrorCodes::NativeSolAccountsRequired.into())
+    } else {
+        Ok(merchant_token_account)
+    }
+}
+
+/// Unwrap merchant payouts collected in the merchant's temporary wSOL account
+/// The account is its own authority (seeds `[b"sol_unwrap", merchant]`), so it
+/// serves every plan of the merchant. It is closed into `payer`, which keeps the
+/// rent it funded and forwards the collected SOL to the merchant
+/// No-op when the instruction did not open the temporary account
+/// 
+/// # Arguments
+/// * `sol_unwrap_account` - Temporary wSOL account holding the payouts
+/// * `sol_unwrap_bump` - Bump of the temporary account's PDA
+/// * `merchant` - Merchant system account receiving SOL
+/// * `payer` - Account that funded the temporary account's rent
+/// * `system_program` - System program, used to forward the SOL
+/// * `token_program` - SPL Token or Token-2022 program
+pub fn unwrap_sol_payout<'info>(
+    sol_unwrap_account: Option<&InterfaceAccount<'info, TokenAccount>>,
+    sol_unwrap_bump: Option<u8>,
+    merchant: Option<&SystemAccount<'info>>,
+    payer: Option<&Signer<'info>>,
+    system_program: Option<&Program<'info, System>>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<()> {
+    let Some(sol_unwrap_account) = sol_unwrap_account else {
+        return Ok(());
+    };
+    let bump = sol_unwrap_bump.ok_or(ErrorCodes::NativeSolAccountsRequired)?;
+    let merchant = merchant.ok_or(ErrorCodes::NativeSolAccountsRequired)?;
+    let payer = payer.ok_or(ErrorCodes::NativeSolAccountsRequired)?;
+    let system_program = system_program.ok_or(ErrorCodes::NativeSolAccountsRequired)?;
+
+    let account_info = sol_unwrap_account.to_account_info();
+    let rent = Rent::get()?.minimum_balance(account_info.data_len());
+    let payout = account_info
+        .lamports()
+        .checked_sub(rent)
+        .ok_or(ErrorCodes::MathOverflow)?;
+
+    let merchant_key = merchant.key();
+    let seeds = &[b"sol_unwrap", merchant_key.as_ref(), &[bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = CloseAccount {
+        account: account_info.clone(),
+        destination: payer.to_account_info(),
+        authority: account_info,
+    };
+    token_interface::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        cpi_accounts,
+        signer_seeds,
+    ))?;
+
+    if payout > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: merchant.to_account_info(),
+                },
+            ),
+            payout,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Transfer tokens out of a merchant token account that delegated to the plan
+/// Uses the merchant plan PDA as signer
+/// 
+/// # Arguments
+/// * `merchant_plan` - The plan PDA (approved delegate of `source`)
+/// * `source` - Merchant token account
+/// * `destination` - Destination token account
+/// * `mint` - The plan's mint
+/// * `token_program` - SPL Token or Token-2022 program
+/// * `amount` - Amount to transfer (no-op when zero)
+pub fn transfer_as_plan_delegate<'info>(
+    merchant_plan: &Account<'info, MerchantPlan>,
+    source: &InterfaceAccount<'info, TokenAccount>,
+    destination: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let merchant_key = merchant_plan.merchant;
+    let mint_key = merchant_plan.mint;
+    let bump = merchant_plan.bump;
+    let seeds = &[
+        b"merchant_plan",
+        merchant_key.as_ref(),
+        mint_key.as_ref(),
+        merchant_plan.plan_id.as_bytes(),
+        &[bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = TransferChecked {
+        from: source.to_account_info(),
+        mint: mint.to_account_info(),
+        to: destination.to_account_info(),
+        authority: merchant_plan.to_account_info(),
+    };
+
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        cpi_accounts,
+        signer_seeds,
+    );
+
+    token_interface::transfer_checked(cpi_ctx, amount, mint.decimals)
+}
+
+/// Refund available through the plan's delegate allowance on a merchant account
+pub fn delegated_refund_allowance(
+    merchant_plan: &Account<MerchantPlan>,
+    merchant_token_account: &TokenAccount,
+) -> u64 {
+    if merchant_token_account.delegate == COption::Some(merchant_plan.key()) {
+        merchant_token_account.delegated_amount
+    } else {
+        0
+    }
+}
@@ -0,0 +1,90 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    transfer_fee::TransferFeeConfig,
+    transfer_hook::TransferHook,
+};
+use anchor_spl::token_2022_extensions::transfer_fee::{
+    harvest_withheld_tokens_to_mint, HarvestWithheldTokensToMint,
+};
+use anchor_spl::token_interface::{get_mint_extension_data, Mint, TokenAccount, TokenInterface};
+use crate::ErrorCodes;
+
+/// The mint's transfer fee config, if it is a Token-2022 mint with one
+fn transfer_fee_config(mint: &InterfaceAccount<Mint>) -> Option<TransferFeeConfig> {
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner != anchor_spl::token_2022::ID {
+        return None;
+    }
+    get_mint_extension_data::<TransferFeeConfig>(&mint_info).ok()
+}
+
+/// Wrapped SOL mint of either token program
+pub fn is_native_mint(mint: &Pubkey) -> bool {
+    *mint == anchor_spl::token::spl_token::native_mint::ID
+        || *mint == anchor_spl::token_2022::spl_token_2022::native_mint::ID
+}
+
+/// Fee withheld by the mint when `amount` is transferred (0 without a transfer fee)
+pub fn transfer_fee(mint: &InterfaceAccount<Mint>, amount: u64) -> Result<u64> {
+    match transfer_fee_config(mint) {
+        Some(config) => config
+            .calculate_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(ErrorCodes::MathOverflow.into()),
+        None => Ok(0),
+    }
+}
+
+/// Fee to add on top of `amount` so the recipient nets exactly `amount`
+pub fn inverse_transfer_fee(mint: &InterfaceAccount<Mint>, amount: u64) -> Result<u64> {
+    if amount == 0 {
+        return Ok(0);
+    }
+
+    match transfer_fee_config(mint) {
+        Some(config) => config
+            .calculate_inverse_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(ErrorCodes::MathOverflow.into()),
+        None => Ok(0),
+    }
+}
+
+/// Amount to send so the recipient nets `amount` after the mint's transfer fee
+pub fn amount_with_transfer_fee(mint: &InterfaceAccount<Mint>, amount: u64) -> Result<u64> {
+    amount
+        .checked_add(inverse_transfer_fee(mint, amount)?)
+        .ok_or(ErrorCodes::MathOverflow.into())
+}
+
+/// Transfer hooks need extra accounts on every transfer, which the protocol
+/// does not pass, so hooked mints can't be used for wallets or plans
+pub fn has_transfer_hook(mint: &InterfaceAccount<Mint>) -> bool {
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner != anchor_spl::token_2022::ID {
+        return false;
+    }
+    get_mint_extension_data::<TransferHook>(&mint_info)
+        .map(|hook| Option::<Pubkey>::from(hook.program_id).is_some())
+        .unwrap_or(false)
+}
+
+/// Move transfer fees withheld in `source` to the mint; Token-2022 won't close
+/// an account that still holds withheld fees
+/// No-op for mints without a transfer fee
+pub fn harvest_withheld_fees<'info>(
+    mint: &InterfaceAccount<'info, Mint>,
+    source: &InterfaceAccount<'info, TokenAccount>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<()> {
+    if transfer_fee_config(mint).is_none() {
+        return Ok(());
+    }
+
+    let cpi_accounts = HarvestWithheldTokensToMint {
+        token_program_id: token_program.to_account_info(),
+        mint: mint.to_account_info(),
+    };
+    harvest_withheld_tokens_to_mint(
+        CpiContext::new(token_program.to_account_info(), cpi_accounts),
+        vec![source.to_account_info()],
+    )
+}
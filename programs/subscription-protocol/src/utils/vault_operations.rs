@@ -3,8 +3,8 @@ use anchor_lang::solana_program::{
     instruction::{AccountMeta, Instruction},
     program::invoke_signed,
 };
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
-use crate::{YieldVault, ErrorCodes};
+use anchor_spl::token_interface::{self, Mint, TokenInterface, TokenAccount, TransferChecked};
+use crate::{SubscriptionWallet, YieldVault, ErrorCodes};
 
 pub const JUPITER_LENDING_PROGRAM_DEVNET: &str = "7tjE28izRUjzmxC1QNXnNwcc4N82CNYCexf3k8mw67s3";
 pub const JUPITER_LIQUIDITY_PROGRAM_DEVNET: &str = "5uDkCoM96pwGYhAUucvCzLfm5UcjVRuxz6gH81RnRBmL";
@@ -34,8 +34,8 @@ fn get_withdraw_discriminator() -> Vec<u8> {
 pub fn get_vault_total_value(
     jupiter_lending: AccountInfo,
     vault: &YieldVault,
-    vault_buffer_account: Option<&Account<TokenAccount>>,
-    vault_ftoken_account: Option<&Account<TokenAccount>>,
+    vault_buffer_account: Option<&InterfaceAccount<TokenAccount>>,
+    vault_ftoken_account: Option<&InterfaceAccount<TokenAccount>>,
 ) -> Result<u64> {
     let mut total_value = 0u64;
     
@@ -99,14 +99,56 @@ pub fn get_vault_total_value(
     Ok(total_value)
 }
 
+/// Value of a wallet's yield shares, counting the whole vault position
+/// (buffer and Jupiter Lend). Wallets with yield shares must pass all vault
+/// accounts; wallets without any are worth 0 and need none.
+pub fn wallet_yield_value(
+    wallet: &SubscriptionWallet,
+    yield_vault: Option<&Account<YieldVault>>,
+    vault_buffer: Option<&InterfaceAccount<TokenAccount>>,
+    jupiter_ftoken_account: Option<&InterfaceAccount<TokenAccount>>,
+    jupiter_lending: Option<&AccountInfo>,
+) -> Result<u64> {
+    if !wallet.is_yield_enabled || wallet.yield_shares == 0 {
+        return Ok(0);
+    }
+
+    let vault = yield_vault.ok_or(ErrorCodes::YieldAccountsRequired)?;
+    let vault_buffer = vault_buffer.ok_or(ErrorCodes::YieldAccountsRequired)?;
+    let jupiter_ftoken_account = jupiter_ftoken_account.ok_or(ErrorCodes::YieldAccountsRequired)?;
+    let jupiter_lending = jupiter_lending.ok_or(ErrorCodes::YieldAccountsRequired)?;
+    require!(
+        vault_buffer.key() == vault.usdc_buffer,
+        ErrorCodes::InvalidVaultBuffer
+    );
+    require!(
+        jupiter_ftoken_account.key() == vault.jupiter_ftoken_account,
+        ErrorCodes::InvalidFTokenAccount
+    );
+    require!(
+        jupiter_lending.key() == vault.jupiter_lending,
+        ErrorCodes::InvalidJupiterLendAccount
+    );
+
+    let total_vault_value = get_vault_total_value(
+        jupiter_lending.clone(),
+        vault,
+        Some(vault_buffer),
+        Some(jupiter_ftoken_account),
+    )?;
+
+    Ok(wallet.calculate_yield_value(total_vault_value, vault.total_shares_issued))
+}
+
 /// Withdraw USDC from vault buffer to a destination account
 /// Uses PDA signing to authorize the transfer
 pub fn withdraw_from_vault_internal<'info>(
     vault_info: AccountInfo<'info>,
     vault: &YieldVault,
-    vault_buffer: &Account<'info, TokenAccount>,
-    destination: &Account<'info, TokenAccount>,
-    token_program: &Program<'info, Token>,
+    vault_buffer: &InterfaceAccount<'info, TokenAccount>,
+    destination: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
     amount: u64,
 ) -> Result<()> {
     let mint_key = vault.mint;
@@ -118,8 +160,9 @@ pub fn withdraw_from_vault_internal<'info>(
     ];
     let signer = &[&seeds[..]];
 
-    let cpi_accounts = Transfer {
+    let cpi_accounts = TransferChecked {
         from: vault_buffer.to_account_info(),
+        mint: mint.to_account_info(),
         to: destination.to_account_info(),
         authority: vault_info,
     };
@@ -130,7 +173,7 @@ pub fn withdraw_from_vault_internal<'info>(
         signer,
     );
     
-    token::transfer(cpi_ctx, amount)?;
+    token_interface::transfer_checked(cpi_ctx, amount, mint.decimals)?;
     Ok(())
 }
 
@@ -141,14 +184,14 @@ pub fn withdraw_from_vault_internal<'info>(
 /// * `from` - Source token account (vault buffer)
 /// * `recipient_ftoken_account` - Destination for fTokens
 /// * `jupiter_accounts` - Jupiter Lend specific accounts
-/// * `token_program` - SPL Token program
+/// * `token_program` - SPL Token or Token-2022 program
 /// * `amount` - Amount to deposit
 pub fn deposit_to_jupiter_lend_internal<'info>(
     vault: &Account<'info, YieldVault>,
-    from: &Account<'info, TokenAccount>,
-    recipient_ftoken_account: &Account<'info, TokenAccount>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    recipient_ftoken_account: &InterfaceAccount<'info, TokenAccount>,
     jupiter_accounts: &JupiterLendAccounts<'info>,
-    token_program: &Program<'info, Token>,
+    token_program: &Interface<'info, TokenInterface>,
     amount: u64,
 ) -> Result<()> {
     let mint_key = vault.mint;
@@ -241,14 +284,14 @@ pub fn deposit_to_jupiter_lend_internal<'info>(
 /// * `owner_ftoken_account` - Source fToken account (owned by vault)
 /// * `recipient_token_account` - Destination for underlying tokens (vault buffer)
 /// * `jupiter_accounts` - Jupiter Lend specific accounts
-/// * `token_program` - SPL Token program
+/// * `token_program` - SPL Token or Token-2022 program
 /// * `amount` - Amount of underlying assets to withdraw
 pub fn withdraw_from_jupiter_lend_internal<'info>(
     vault: &Account<'info, YieldVault>,
-    owner_ftoken_account: &Account<'info, TokenAccount>,
-    recipient_token_account: &Account<'info, TokenAccount>,
+    owner_ftoken_account: &InterfaceAccount<'info, TokenAccount>,
+    recipient_token_account: &InterfaceAccount<'info, TokenAccount>,
     jupiter_accounts: &JupiterLendAccounts<'info>,
-    token_program: &Program<'info, Token>,
+    token_program: &Interface<'info, TokenInterface>,
     amount: u64,
 ) -> Result<()> {
     let mint_key = vault.mint;
@@ -337,6 +380,15 @@ pub fn withdraw_from_jupiter_lend_internal<'info>(
     Ok(())
 }
 
+/// Number of accounts in `JupiterLendAccounts`
+pub const JUPITER_LEND_ACCOUNT_COUNT: usize = 15;
+
+/// Jupiter Lend program on mainnet or devnet
+pub fn is_jupiter_lending_program(program_id: &Pubkey) -> bool {
+    let program_id = program_id.to_string();
+    program_id == JUPITER_LENDING_PROGRAM_MAINNET || program_id == JUPITER_LENDING_PROGRAM_DEVNET
+}
+
 pub struct JupiterLendAccounts<'info> {
     // Token accounts
     pub mint: AccountInfo<'info>,
@@ -361,4 +413,60 @@ pub struct JupiterLendAccounts<'info> {
     pub lending_program: AccountInfo<'info>,
     pub associated_token_program: AccountInfo<'info>,
     pub system_program: AccountInfo<'info>,
-}
\ No newline at end of file
+}
+impl<'info> JupiterLendAccounts<'info> {
+    /// Read Jupiter Lend accounts passed in `remaining_accounts`, in field order
+    /// The vault PDA signs the CPI, so the program, lending account and mint
+    /// must be the vault's; Jupiter Lend checks the rest
+    pub fn from_remaining_accounts(
+        vault: &YieldVault,
+        accounts: &[AccountInfo<'info>],
+    ) -> Result<Self> {
+        let [
+            mint,
+            f_token_mint,
+            lending_admin,
+            lending,
+            supply_token_reserves_liquidity,
+            lending_supply_position_on_liquidity,
+            rate_model,
+            jupiter_vault,
+            liquidity,
+            liquidity_program,
+            rewards_rate_model,
+            claim_account,
+            lending_program,
+            associated_token_program,
+            system_program,
+        ] = accounts else {
+            return Err(ErrorCodes::InvalidJupiterLendAccount.into());
+        };
+
+        require!(
+            mint.key() == vault.mint
+                && lending.key() == vault.jupiter_lending
+                && is_jupiter_lending_program(&lending_program.key())
+                && associated_token_program.key() == anchor_spl::associated_token::ID
+                && system_program.key() == anchor_lang::system_program::ID,
+            ErrorCodes::InvalidJupiterLendAccount
+        );
+
+        Ok(Self {
+            mint: mint.clone(),
+            f_token_mint: f_token_mint.clone(),
+            lending_admin: lending_admin.clone(),
+            lending: lending.clone(),
+            supply_token_reserves_liquidity: supply_token_reserves_liquidity.clone(),
+            lending_supply_position_on_liquidity: lending_supply_position_on_liquidity.clone(),
+            rate_model: rate_model.clone(),
+            jupiter_vault: jupiter_vault.clone(),
+            liquidity: liquidity.clone(),
+            liquidity_program: liquidity_program.clone(),
+            rewards_rate_model: rewards_rate_model.clone(),
+            claim_account: claim_account.clone(),
+            lending_program: lending_program.clone(),
+            associated_token_program: associated_token_program.clone(),
+            system_program: system_program.clone(),
+        })
+    }
+}